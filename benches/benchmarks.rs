@@ -1,5 +1,9 @@
-use criterion::{black_box, criterion_group, criterion_main, Criterion};
-use triple_buffer::TripleBuffer;
+use criterion::{black_box, criterion_group, criterion_main, BatchSize, BenchmarkId, Criterion};
+use std::sync::{
+    atomic::{AtomicU8, Ordering},
+    Mutex, RwLock,
+};
+use triple_buffer::{AtomicTripleBuffer, TripleBuffer};
 
 pub fn benchmark(c: &mut Criterion) {
     let (mut input, mut output) = TripleBuffer::<u8>::default().split();
@@ -36,6 +40,24 @@ pub fn benchmark(c: &mut Criterion) {
                 *output.read()
             })
         });
+        uncontended.bench_function("clean updated + read", |b| {
+            b.iter(|| output.updated().then(|| *output.read()))
+        });
+        uncontended.bench_function("clean poll_and_read", |b| {
+            b.iter(|| output.poll_and_read().copied())
+        });
+        uncontended.bench_function("send + updated + read", |b| {
+            b.iter(|| {
+                input.write(black_box(0));
+                output.updated().then(|| *output.read())
+            })
+        });
+        uncontended.bench_function("send + poll_and_read", |b| {
+            b.iter(|| {
+                input.write(black_box(0));
+                output.poll_and_read().copied()
+            })
+        });
     }
 
     {
@@ -76,5 +98,220 @@ pub fn benchmark(c: &mut Criterion) {
     }
 }
 
-criterion_group!(benches, benchmark);
+/// Compare moving new values into the buffer with updating it in place, for
+/// payloads which own heap allocations of various sizes
+pub fn payloads(c: &mut Criterion) {
+    let mut group = c.benchmark_group("Vec<f64> payload");
+    for len in [16, 1024, 65536] {
+        let (mut input, _output) = TripleBuffer::new(&vec![0.0f64; len]).split();
+
+        // Building a new value and moving it in, as with the write() interface
+        group.bench_with_input(BenchmarkId::new("build + write", len), &len, |b, &len| {
+            b.iter(|| input.write(vec![black_box(1.0); len]))
+        });
+
+        // Moving in a value that was built beforehand, which only measures the
+        // cost of the move and of dropping the input buffer's former contents
+        group.bench_with_input(BenchmarkId::new("write", len), &len, |b, &len| {
+            b.iter_batched(
+                || vec![1.0; len],
+                |value| input.write(value),
+                BatchSize::SmallInput,
+            )
+        });
+
+        // Updating the input buffer in place, reusing its allocation
+        group.bench_with_input(
+            BenchmarkId::new("in-place update + publish", len),
+            &len,
+            |b, &len| {
+                b.iter(|| {
+                    let buffer = input.input_buffer();
+                    buffer.clear();
+                    buffer.resize(len, black_box(1.0));
+                    input.publish();
+                })
+            },
+        );
+    }
+}
+
+/// Same as above, but for the atomic triple buffer used for small values
+pub fn atomic(c: &mut Criterion) {
+    let (mut input, mut output) = AtomicTripleBuffer::<u8>::default().split();
+
+    {
+        let mut uncontended = c.benchmark_group("atomic uncontended");
+        uncontended.bench_function("clean receive", |b| b.iter(|| output.read()));
+        uncontended.bench_function("send", |b| b.iter(|| input.write(black_box(0))));
+        uncontended.bench_function("transmit", |b| {
+            b.iter(|| {
+                input.write(black_box(0));
+                output.read()
+            })
+        });
+    }
+
+    {
+        let mut read_contended = c.benchmark_group("atomic read contention");
+        testbench::run_under_contention(
+            || black_box(output.read()),
+            || {
+                read_contended.bench_function("send", |b| b.iter(|| input.write(black_box(0))));
+            },
+        );
+    }
+
+    {
+        let mut write_contended = c.benchmark_group("atomic write contention");
+        testbench::run_under_contention(
+            || input.write(black_box(0)),
+            || {
+                write_contended.bench_function("receive", |b| b.iter(|| output.read()));
+            },
+        );
+    }
+}
+
+/// Compare the unconditional atomic swap that `Input::publish()` uses to update
+/// the back-buffer information with a `compare_exchange_weak` loop
+///
+/// Both model the synchronization protocol on a bare atomic, using the same bit
+/// layout as the triple buffer, since the real back-buffer information is not
+/// accessible from outside of the crate.
+pub fn publish_primitives(c: &mut Criterion) {
+    bench_publish(c, "swap publish", |back_info, input_idx| {
+        let former_back_info = back_info.swap(*input_idx | DIRTY_BIT, Ordering::AcqRel);
+        *input_idx = former_back_info & INDEX_MASK;
+    });
+    bench_publish(c, "CAS publish", |back_info, input_idx| {
+        let mut expected = back_info.load(Ordering::Relaxed);
+        loop {
+            match back_info.compare_exchange_weak(
+                expected,
+                *input_idx | DIRTY_BIT,
+                Ordering::AcqRel,
+                Ordering::Relaxed,
+            ) {
+                Ok(former_back_info) => {
+                    *input_idx = former_back_info & INDEX_MASK;
+                    break;
+                }
+                Err(actual) => expected = actual,
+            }
+        }
+    });
+}
+
+/// Mask used to extract the back-buffer index from the back-buffer information
+const INDEX_MASK: u8 = 0b11;
+
+/// Bit of the back-buffer information which is set by a publish
+const DIRTY_BIT: u8 = 0b100;
+
+/// Benchmark a way to publish updates, given the atomic back-buffer information
+/// and the producer's input buffer index
+fn bench_publish(c: &mut Criterion, name: &str, publish: impl Fn(&AtomicU8, &mut u8) + Sync) {
+    // Same initial buffer indices as a fresh triple buffer
+    let back_info = AtomicU8::new(0);
+    let mut input_idx = 1;
+    let mut output_idx = 2;
+    let update = |output_idx: &mut u8| {
+        if back_info.load(Ordering::Relaxed) & DIRTY_BIT != 0 {
+            let former_back_info = back_info.swap(*output_idx, Ordering::AcqRel);
+            *output_idx = former_back_info & INDEX_MASK;
+        }
+    };
+
+    {
+        let mut uncontended = c.benchmark_group(format!("{name} uncontended"));
+        uncontended.bench_function("publish", |b| {
+            b.iter(|| publish(&back_info, &mut input_idx))
+        });
+        uncontended.bench_function("publish + dirty update", |b| {
+            b.iter(|| {
+                publish(&back_info, &mut input_idx);
+                update(&mut output_idx);
+            })
+        });
+    }
+
+    {
+        let mut read_contended = c.benchmark_group(format!("{name} read contention"));
+        testbench::run_under_contention(
+            || update(&mut output_idx),
+            || {
+                read_contended.bench_function("publish", |b| {
+                    b.iter(|| publish(&back_info, &mut input_idx))
+                });
+            },
+        );
+    }
+}
+
+/// Compare with the lock-based alternatives to triple buffering, using the same
+/// single-producer single-consumer access patterns as above
+pub fn locks(c: &mut Criterion) {
+    let mutex = Mutex::new(0u8);
+    bench_lock(
+        c,
+        "mutex",
+        || *mutex.lock().unwrap() = black_box(0),
+        || *mutex.lock().unwrap(),
+    );
+
+    let rwlock = RwLock::new(0u8);
+    bench_lock(
+        c,
+        "rwlock",
+        || *rwlock.write().unwrap() = black_box(0),
+        || *rwlock.read().unwrap(),
+    );
+}
+
+/// Benchmark a lock-protected value, given ways to write and read it
+fn bench_lock(
+    c: &mut Criterion,
+    name: &str,
+    write: impl Fn() + Sync,
+    read: impl Fn() -> u8 + Sync,
+) {
+    {
+        let mut uncontended = c.benchmark_group(format!("{name} uncontended"));
+        uncontended.bench_function("send", |b| b.iter(&write));
+        uncontended.bench_function("receive", |b| b.iter(&read));
+        uncontended.bench_function("transmit", |b| {
+            b.iter(|| {
+                write();
+                read()
+            })
+        });
+    }
+
+    {
+        let mut read_contended = c.benchmark_group(format!("{name} read contention"));
+        testbench::run_under_contention(
+            || black_box(read()),
+            || {
+                read_contended.bench_function("send", |b| b.iter(&write));
+            },
+        );
+    }
+
+    {
+        let mut write_contended = c.benchmark_group(format!("{name} write contention"));
+        testbench::run_under_contention(&write, || {
+            write_contended.bench_function("receive", |b| b.iter(&read));
+        });
+    }
+}
+
+criterion_group!(
+    benches,
+    benchmark,
+    payloads,
+    atomic,
+    publish_primitives,
+    locks
+);
 criterion_main!(benches);
@@ -0,0 +1,112 @@
+//! Sending DSP parameters from a control thread to a real-time audio callback
+//!
+//! Audio callbacks run under hard real-time constraints: they must produce a
+//! buffer of samples before a deadline, so they must never block, allocate or
+//! take locks. Triple buffers are a good fit for sending them parameters,
+//! because the consumer side never does any of those things, as long as the
+//! producer only uses non-blocking writes like `write()`.
+//!
+//! Here, a control thread (which would normally react to user input) slowly
+//! sweeps the parameters of a one-pole low-pass filter, and a mock audio
+//! callback reads the latest parameters at the start of each buffer.
+
+use std::{f32::consts::PI, thread, time::Duration};
+use triple_buffer::{triple_buffer, Output};
+
+/// Sample rate of the mock audio device
+const SAMPLE_RATE: f32 = 48_000.0;
+
+/// Number of samples per audio buffer
+const BUFFER_SIZE: usize = 256;
+
+/// Number of audio buffers to process before exiting
+const NUM_BUFFERS: usize = 200;
+
+/// Parameters of the audio processing, computed by the control thread
+#[derive(Clone, Copy, Debug)]
+struct Params {
+    /// Output volume
+    gain: f32,
+
+    /// Cutoff frequency of the low-pass filter, in Hz
+    cutoff: f32,
+}
+
+/// State of the audio processing, owned by the audio callback
+struct AudioProcessor {
+    /// Parameters from the control thread
+    params: Output<Params>,
+
+    /// Phase of the input oscillator, in [0; 1[
+    phase: f32,
+
+    /// Last output of the low-pass filter
+    filter_state: f32,
+}
+//
+impl AudioProcessor {
+    /// Mock audio callback, called by the audio device for each buffer
+    ///
+    /// This function must not block, allocate or lock. Reading from the triple
+    /// buffer satisfies all of these constraints.
+    ///
+    fn process(&mut self, output: &mut [f32]) {
+        // Fetch the latest parameters once per buffer
+        let Params { gain, cutoff } = *self.params.read();
+        let alpha = 1.0 - (-2.0 * PI * cutoff / SAMPLE_RATE).exp();
+
+        // Filter a sawtooth wave with these parameters
+        for sample in output {
+            let input = 2.0 * self.phase - 1.0;
+            self.phase = (self.phase + 220.0 / SAMPLE_RATE).fract();
+            self.filter_state += alpha * (input - self.filter_state);
+            *sample = gain * self.filter_state;
+        }
+    }
+}
+
+fn main() {
+    // Set up the triple buffer with some initial parameters
+    let initial = Params {
+        gain: 0.5,
+        cutoff: 200.0,
+    };
+    let (mut params_input, params_output) = triple_buffer(&initial);
+
+    // The control thread sweeps the cutoff frequency up, then stops once the
+    // audio processor has been dropped
+    let control = thread::spawn(move || {
+        let mut cutoff = initial.cutoff;
+        while params_input.reader_alive() {
+            cutoff = (cutoff * 1.05).min(10_000.0);
+            params_input.write(Params {
+                gain: initial.gain,
+                cutoff,
+            });
+            thread::sleep(Duration::from_millis(1));
+        }
+    });
+
+    // The audio thread calls the processing callback for each buffer
+    let audio = thread::spawn(move || {
+        let mut processor = AudioProcessor {
+            params: params_output,
+            phase: 0.0,
+            filter_state: 0.0,
+        };
+        let mut buffer = [0.0; BUFFER_SIZE];
+        let mut peak = 0.0f32;
+        for _ in 0..NUM_BUFFERS {
+            processor.process(&mut buffer);
+            peak = buffer.iter().fold(peak, |peak, s| peak.max(s.abs()));
+            thread::sleep(Duration::from_secs_f32(BUFFER_SIZE as f32 / SAMPLE_RATE));
+        }
+        let final_params = *processor.params.read();
+        (peak, final_params)
+    });
+
+    let (peak, final_params) = audio.join().unwrap();
+    control.join().unwrap();
+    println!("Processed {NUM_BUFFERS} buffers, peak amplitude {peak:.3}");
+    println!("Final parameters: {final_params:?}");
+}
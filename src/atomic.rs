@@ -0,0 +1,164 @@
+//! Atomic backends for the back-buffer information
+//!
+//! The synchronization protocol of the triple buffer only needs a handful of
+//! atomic operations on the back-buffer information. They are abstracted away
+//! behind the `BackInfoAtomic` trait, so that the buffer swaps performed by
+//! `publish()` and `update()` are written once, in `publish_swap()` and
+//! `update_swap()`, and alternate backends (e.g. model checkers, or emulation
+//! of atomics on targets that lack them) only need to implement this trait.
+
+use crate::{BackBufferInfo, BufferIndex};
+use core::{
+    cell::Cell,
+    sync::atomic::{AtomicU8, Ordering},
+};
+
+/// Atomic operations that the triple buffer protocol relies on
+///
+/// Backends which are not `Sync` can only be used by single-threaded triple
+/// buffers, where memory orderings are irrelevant.
+///
+pub(crate) trait BackInfoAtomic {
+    /// Build an atomic cell with the specified initial value
+    fn new(value: BackBufferInfo) -> Self;
+
+    /// Read the current value
+    fn load(&self, order: Ordering) -> BackBufferInfo;
+
+    /// Overwrite the current value
+    fn store(&self, value: BackBufferInfo, order: Ordering);
+
+    /// Overwrite the current value, returning the previous one
+    fn swap(&self, value: BackBufferInfo, order: Ordering) -> BackBufferInfo;
+
+    /// Set some bits of the current value, returning the previous one
+    #[cfg(any(feature = "async", feature = "blocking"))]
+    fn fetch_or(&self, bits: u8, order: Ordering) -> BackBufferInfo;
+
+    /// Clear some bits of the current value, returning the previous one
+    #[cfg(any(feature = "async", feature = "blocking"))]
+    fn fetch_and(&self, bits: u8, order: Ordering) -> BackBufferInfo;
+}
+
+/// Swap the producer's input buffer with the back-buffer, setting the dirty bit
+///
+/// `input_idx` is updated to designate the producer's new input buffer, and
+/// the former back-buffer information is returned, so that the caller can
+/// tell whether unread data was overwritten.
+///
+/// `order` must be at least `AcqRel` for thread-safe backends, because...
+///
+/// - The producer's accesses to the old buffer must not be reordered after
+///   this operation (which mandates Release ordering), otherwise they could
+///   race with the consumer accessing the freshly published buffer.
+/// - The producer's accesses from the new buffer must not be reordered before
+///   this operation (which mandates Consume ordering, that is best
+///   approximated by Acquire in Rust), otherwise they would race with the
+///   consumer accessing the buffer as well before switching to another buffer.
+///   * This reordering may seem paradoxical, but could happen if the compiler
+///     or CPU correctly speculated the new buffer's index before that index is
+///     actually read, as well as on weird hardware with incoherent caches like
+///     GPUs or old DEC Alpha where keeping data in sync across cores requires
+///     manual action.
+///
+pub(crate) fn publish_swap(
+    back_info: &impl BackInfoAtomic,
+    input_idx: &mut BufferIndex,
+    order: Ordering,
+) -> BackBufferInfo {
+    #[cfg(feature = "fuzz-delays")]
+    crate::delay::delay_point();
+    let former_back_info = back_info.swap(BackBufferInfo::clean(*input_idx).with_dirty(), order);
+    *input_idx = former_back_info.index();
+    former_back_info
+}
+
+/// Swap the consumer's output buffer with the back-buffer, if it is dirty
+///
+/// If an update was pending, `output_idx` is updated to designate it, and the
+/// former back-buffer information is returned. Otherwise, nothing happens.
+///
+/// `order` must be at least `AcqRel` for thread-safe backends, for the same
+/// reasons as in `publish_swap()`: the consumer's accesses to its old output
+/// buffer must not race with the producer reusing it, and its accesses to the
+/// new output buffer must not race with the producer writing into it before
+/// publishing it.
+///
+pub(crate) fn update_swap(
+    back_info: &impl BackInfoAtomic,
+    output_idx: &mut BufferIndex,
+    order: Ordering,
+) -> Option<BackBufferInfo> {
+    if !back_info.load(Ordering::Relaxed).is_dirty() {
+        return None;
+    }
+    #[cfg(feature = "fuzz-delays")]
+    crate::delay::delay_point();
+    let former_back_info = back_info.swap(BackBufferInfo::clean(*output_idx), order);
+    *output_idx = former_back_info.index();
+    Some(former_back_info)
+}
+
+/// Backend based on the atomic types from `core`
+#[derive(Debug)]
+pub(crate) struct CoreAtomic(AtomicU8);
+//
+impl BackInfoAtomic for CoreAtomic {
+    fn new(value: BackBufferInfo) -> Self {
+        Self(AtomicU8::new(value.0))
+    }
+
+    fn load(&self, order: Ordering) -> BackBufferInfo {
+        BackBufferInfo(self.0.load(order))
+    }
+
+    fn store(&self, value: BackBufferInfo, order: Ordering) {
+        self.0.store(value.0, order)
+    }
+
+    fn swap(&self, value: BackBufferInfo, order: Ordering) -> BackBufferInfo {
+        BackBufferInfo(self.0.swap(value.0, order))
+    }
+
+    #[cfg(any(feature = "async", feature = "blocking"))]
+    fn fetch_or(&self, bits: u8, order: Ordering) -> BackBufferInfo {
+        BackBufferInfo(self.0.fetch_or(bits, order))
+    }
+
+    #[cfg(any(feature = "async", feature = "blocking"))]
+    fn fetch_and(&self, bits: u8, order: Ordering) -> BackBufferInfo {
+        BackBufferInfo(self.0.fetch_and(bits, order))
+    }
+}
+
+/// Backend for single-threaded triple buffers, which need no atomics
+#[derive(Debug)]
+pub(crate) struct LocalCell(Cell<BackBufferInfo>);
+//
+impl BackInfoAtomic for LocalCell {
+    fn new(value: BackBufferInfo) -> Self {
+        Self(Cell::new(value))
+    }
+
+    fn load(&self, _order: Ordering) -> BackBufferInfo {
+        self.0.get()
+    }
+
+    fn store(&self, value: BackBufferInfo, _order: Ordering) {
+        self.0.set(value)
+    }
+
+    fn swap(&self, value: BackBufferInfo, _order: Ordering) -> BackBufferInfo {
+        self.0.replace(value)
+    }
+
+    #[cfg(any(feature = "async", feature = "blocking"))]
+    fn fetch_or(&self, bits: u8, _order: Ordering) -> BackBufferInfo {
+        self.0.replace(BackBufferInfo(self.0.get().0 | bits))
+    }
+
+    #[cfg(any(feature = "async", feature = "blocking"))]
+    fn fetch_and(&self, bits: u8, _order: Ordering) -> BackBufferInfo {
+        self.0.replace(BackBufferInfo(self.0.get().0 & bits))
+    }
+}
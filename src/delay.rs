@@ -0,0 +1,72 @@
+//! Randomized delay injection, used to make stress tests more effective
+//!
+//! Memory ordering bugs only manifest when threads interleave in specific ways,
+//! which may happen very rarely if the critical code sections are short. By
+//! waiting for a random amount of time at the points of the synchronization
+//! protocol where interleavings matter, we make these rare interleavings more
+//! likely to be observed by the concurrent tests.
+//!
+//! The pseudo-random sequence of delays can be controlled by setting the
+//! `TRIPLE_BUFFER_DELAY_SEED` environment variable to an integer.
+
+use std::{
+    cell::Cell,
+    collections::hash_map::DefaultHasher,
+    hash::{Hash, Hasher},
+    hint, thread,
+};
+
+thread_local! {
+    /// State of this thread's pseudo-random number generator
+    static RNG_STATE: Cell<u64> = Cell::new(initial_state());
+}
+
+/// Name of the environment variable used to seed the random delays
+const SEED_VAR: &str = "TRIPLE_BUFFER_DELAY_SEED";
+
+/// Compute the initial RNG state of the active thread
+fn initial_state() -> u64 {
+    // Start from the user-specified seed, if any...
+    let seed = std::env::var(SEED_VAR)
+        .ok()
+        .and_then(|seed| seed.parse::<u64>().ok())
+        .unwrap_or(0x2545_f491_4f6c_dd1d);
+
+    // ...and mix in the thread's identity so that threads do not wait in
+    // lockstep, making sure that the xorshift state does not end up being 0.
+    let mut hasher = DefaultHasher::new();
+    seed.hash(&mut hasher);
+    thread::current().id().hash(&mut hasher);
+    hasher.finish() | 1
+}
+
+/// Generate a pseudo-random number using the xorshift64* algorithm
+fn next_random() -> u64 {
+    RNG_STATE.with(|state| {
+        let mut x = state.get();
+        x ^= x >> 12;
+        x ^= x << 25;
+        x ^= x >> 27;
+        state.set(x);
+        x.wrapping_mul(0x2545_f491_4f6c_dd1d)
+    })
+}
+
+/// Wait for a random amount of time
+///
+/// Most of the time, this will either not wait or spin for a little while, but
+/// sometimes the thread will also yield to the OS scheduler.
+///
+pub(crate) fn delay_point() {
+    let random = next_random();
+    let spins = (random >> 8) % 256;
+    match random % 8 {
+        0..=3 => {}
+        4..=6 => {
+            for _ in 0..spins {
+                hint::spin_loop();
+            }
+        }
+        _ => thread::yield_now(),
+    }
+}
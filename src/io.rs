@@ -0,0 +1,104 @@
+//! Byte stream adapter for triple buffers that carry byte frames
+
+use crate::Output;
+use std::io::{self, Read};
+
+/// Adapter which exposes the output of a triple buffer as a byte stream
+///
+/// When the producer publishes byte frames (e.g. `Vec<u8>` or `[u8; N]`), this
+/// wrapper lets you consume them through the standard `std::io::Read` trait.
+/// Bytes from the current frame are returned first. Once the current frame is
+/// exhausted, the reader fetches the latest frame from the producer, if any.
+///
+/// A frame is never interrupted by a newer one: if the producer publishes
+/// while a frame is being read, the new frame will only be picked up once the
+/// current one has been fully read out. As usual with triple buffers,
+/// intermediate frames may be dropped if the producer is faster than you.
+///
+/// When the current frame has been fully read and no new frame is available,
+/// `read()` returns `Ok(0)`. This means that `read_to_end()` will copy out
+/// everything that is available right now, and that you can try reading again
+/// later to get the next frame.
+///
+#[derive(Debug)]
+pub struct OutputReader<T: AsRef<[u8]> + Send> {
+    /// Output interface of the underlying triple buffer
+    output: Output<T>,
+
+    /// Number of bytes from the current output buffer that were already read
+    position: usize,
+}
+//
+impl<T: AsRef<[u8]> + Send> OutputReader<T> {
+    /// Wrap the output interface of a triple buffer into a byte reader
+    ///
+    /// The first bytes to be read will be those of the current output buffer.
+    pub fn new(output: Output<T>) -> Self {
+        Self {
+            output,
+            position: 0,
+        }
+    }
+
+    /// Get back the underlying output interface
+    pub fn into_inner(self) -> Output<T> {
+        self.output
+    }
+}
+//
+impl<T: AsRef<[u8]> + Send> Read for OutputReader<T> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        // If the current frame has been fully read, try to fetch a new one
+        if self.position >= self.output.peek_output_buffer().as_ref().len() {
+            if !self.output.update() {
+                return Ok(0);
+            }
+            self.position = 0;
+        }
+
+        // Copy as much of the remaining frame data as we can
+        let remaining = &self.output.peek_output_buffer().as_ref()[self.position..];
+        let len = remaining.len().min(buf.len());
+        buf[..len].copy_from_slice(&remaining[..len]);
+        self.position += len;
+        Ok(len)
+    }
+}
+
+/// Unit tests
+#[cfg(test)]
+mod tests {
+    use super::OutputReader;
+    use crate::TripleBuffer;
+    use std::io::Read;
+
+    /// Check that frames are read out in full, then updated
+    #[test]
+    fn read_frames() {
+        let (mut input, output) = TripleBuffer::new(&vec![1u8, 2, 3]).split();
+        let mut reader = OutputReader::new(output);
+
+        // The initial frame can be read out in several steps
+        let mut buf = [0; 2];
+        assert_eq!(reader.read(&mut buf).unwrap(), 2);
+        assert_eq!(buf, [1, 2]);
+
+        // A frame published in the meantime does not interrupt the current one
+        input.write(vec![4, 5]);
+        assert_eq!(reader.read(&mut buf).unwrap(), 1);
+        assert_eq!(buf[0], 3);
+
+        // Once the current frame is exhausted, the next one is fetched
+        let mut out = Vec::new();
+        reader.read_to_end(&mut out).unwrap();
+        assert_eq!(out, [4, 5]);
+
+        // If there is no new frame, the reader reports end of data...
+        assert_eq!(reader.read(&mut buf).unwrap(), 0);
+
+        // ...until a new frame comes in
+        input.write(vec![6]);
+        assert_eq!(reader.read(&mut buf).unwrap(), 1);
+        assert_eq!(buf[0], 6);
+    }
+}
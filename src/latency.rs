@@ -0,0 +1,259 @@
+//! Timing statistics, enabled by the `latency-stats` feature
+
+use core::convert::TryFrom;
+use std::time::Duration;
+
+/// Number of histogram buckets, enough to cover the full range of `u64` nanoseconds
+const NUM_BUCKETS: usize = 64;
+
+/// Histogram of the time elapsed between publishing and fetching updates
+///
+/// Every time `Output::update()` fetches a new update, the time elapsed since
+/// the producer published that update is recorded here. Updates which were
+/// overwritten by the producer before the consumer could fetch them are not
+/// recorded, since they never reached the consumer.
+///
+/// Latencies are recorded in power-of-two buckets of nanoseconds: bucket 0
+/// holds latencies below 2ns, and bucket `i > 0` holds latencies in the
+/// `[2^i, 2^(i+1))` ns range. This keeps recording cheap and the memory
+/// footprint constant, at the expense of precision.
+///
+/// The histogram is private to the consumer, so recording into it does not
+/// require any extra synchronization.
+///
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct LatencyHistogram {
+    /// Number of latencies recorded into each bucket
+    buckets: [u64; NUM_BUCKETS],
+
+    /// Highest latency that was recorded so far
+    max: Duration,
+}
+//
+impl LatencyHistogram {
+    /// Build an empty histogram
+    pub(crate) fn new() -> Self {
+        Self {
+            buckets: [0; NUM_BUCKETS],
+            max: Duration::ZERO,
+        }
+    }
+
+    /// Record a latency measurement
+    pub(crate) fn record(&mut self, latency: Duration) {
+        let nanos = u64::try_from(latency.as_nanos()).unwrap_or(u64::MAX);
+        let bucket = (u64::BITS - 1).saturating_sub(nanos.leading_zeros()) as usize;
+        self.buckets[bucket] += 1;
+        self.max = self.max.max(latency);
+    }
+
+    /// Total number of latencies that were recorded
+    pub fn count(&self) -> u64 {
+        self.buckets.iter().sum()
+    }
+
+    /// Highest latency that was recorded, if any
+    pub fn max(&self) -> Option<Duration> {
+        (self.count() > 0).then_some(self.max)
+    }
+
+    /// Iterate over non-empty buckets
+    ///
+    /// Each bucket is described by the lower bound of the latencies that it
+    /// holds, and by the number of latencies that it holds.
+    ///
+    pub fn buckets(&self) -> impl Iterator<Item = (Duration, u64)> + '_ {
+        self.buckets
+            .iter()
+            .enumerate()
+            .filter(|(_, &count)| count > 0)
+            .map(|(idx, &count)| (Self::bucket_start(idx), count))
+    }
+
+    /// Upper bound on the given quantile of the latency distribution
+    ///
+    /// `quantile` must be between 0.0 and 1.0. For example, `quantile(0.99)`
+    /// returns a latency which is higher than 99% of recorded latencies.
+    /// Because latencies are bucketed, the result may overestimate the actual
+    /// quantile by up to a factor of two, but it never exceeds `max()`.
+    ///
+    /// Returns `None` if no latency was recorded yet.
+    ///
+    pub fn quantile(&self, quantile: f64) -> Option<Duration> {
+        assert!(
+            (0.0..=1.0).contains(&quantile),
+            "Quantile must be between 0.0 and 1.0"
+        );
+        let count = self.count();
+        if count == 0 {
+            return None;
+        }
+        let threshold = ((quantile * count as f64).ceil() as u64).max(1);
+        let mut seen = 0;
+        for (idx, &bucket_count) in self.buckets.iter().enumerate() {
+            seen += bucket_count;
+            if seen >= threshold {
+                return Some(Self::bucket_end(idx).min(self.max));
+            }
+        }
+        unreachable!("The threshold cannot exceed the total count")
+    }
+
+    /// Lower bound of the latencies held by a bucket
+    fn bucket_start(idx: usize) -> Duration {
+        Duration::from_nanos(if idx == 0 { 0 } else { 1 << idx })
+    }
+
+    /// Upper bound of the latencies held by a bucket
+    fn bucket_end(idx: usize) -> Duration {
+        Duration::from_nanos(1u64.checked_shl(idx as u32 + 1).unwrap_or(u64::MAX))
+    }
+}
+//
+impl Default for LatencyHistogram {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Statistics on the time elapsed between successive reads from the consumer
+///
+/// Every call to `Output::update()`, including the implicit ones performed by
+/// `Output::read()` and friends, counts as a read. Whether a new update was
+/// fetched or not does not matter, so these statistics describe the cadence
+/// of the consumer independently of that of the producer. A consumer which is
+/// meant to read at a steady rate, but ends up being starved or scheduled
+/// irregularly, will show up as a high maximum interval or jitter.
+///
+/// Like `LatencyHistogram`, these statistics are private to the consumer.
+///
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct IntervalStats {
+    /// Number of intervals that were recorded
+    count: u64,
+
+    /// Shortest interval that was recorded so far
+    min: Duration,
+
+    /// Longest interval that was recorded so far
+    max: Duration,
+
+    /// Running mean of the intervals, in nanoseconds
+    mean_nanos: f64,
+
+    /// Running sum of squared deviations from the mean, in squared nanoseconds
+    m2_nanos: f64,
+}
+//
+impl IntervalStats {
+    /// Record the time elapsed between two reads
+    pub(crate) fn record(&mut self, interval: Duration) {
+        // Update the extrema...
+        self.min = if self.count == 0 {
+            interval
+        } else {
+            self.min.min(interval)
+        };
+        self.max = self.max.max(interval);
+
+        // ...then the mean and variance, using Welford's algorithm
+        self.count += 1;
+        let nanos = interval.as_nanos() as f64;
+        let delta = nanos - self.mean_nanos;
+        self.mean_nanos += delta / self.count as f64;
+        self.m2_nanos += delta * (nanos - self.mean_nanos);
+    }
+
+    /// Number of intervals that were recorded
+    ///
+    /// This is one less than the number of reads, since the first read does
+    /// not have a predecessor to measure an interval from.
+    ///
+    pub fn count(&self) -> u64 {
+        self.count
+    }
+
+    /// Shortest interval between two reads, if any
+    pub fn min(&self) -> Option<Duration> {
+        (self.count > 0).then_some(self.min)
+    }
+
+    /// Longest interval between two reads, if any
+    pub fn max(&self) -> Option<Duration> {
+        (self.count > 0).then_some(self.max)
+    }
+
+    /// Average interval between two reads, if any
+    pub fn mean(&self) -> Option<Duration> {
+        (self.count > 0).then(|| Duration::from_nanos(self.mean_nanos.round() as u64))
+    }
+
+    /// Standard deviation of the intervals between two reads, if any
+    pub fn jitter(&self) -> Option<Duration> {
+        (self.count > 0).then(|| {
+            let variance = self.m2_nanos / self.count as f64;
+            Duration::from_nanos(variance.sqrt().round() as u64)
+        })
+    }
+}
+
+/// Unit tests
+#[cfg(test)]
+mod tests {
+    use super::{IntervalStats, LatencyHistogram};
+    use std::time::Duration;
+
+    /// Check that latencies end up in the right buckets
+    #[test]
+    fn histogram() {
+        let mut histogram = LatencyHistogram::new();
+        assert_eq!(histogram.count(), 0);
+        assert_eq!(histogram.max(), None);
+        assert_eq!(histogram.quantile(0.5), None);
+        assert_eq!(histogram.buckets().count(), 0);
+
+        for nanos in [0, 1, 2, 3, 1000, u64::MAX] {
+            histogram.record(Duration::from_nanos(nanos));
+        }
+        histogram.record(Duration::MAX);
+        assert_eq!(histogram.count(), 7);
+        assert_eq!(histogram.max(), Some(Duration::MAX));
+        assert_eq!(
+            histogram.buckets().collect::<Vec<_>>(),
+            [
+                (Duration::ZERO, 2),
+                (Duration::from_nanos(2), 2),
+                (Duration::from_nanos(512), 1),
+                (Duration::from_nanos(1 << 63), 2),
+            ]
+        );
+
+        assert_eq!(histogram.quantile(0.0), Some(Duration::from_nanos(2)));
+        assert_eq!(histogram.quantile(0.5), Some(Duration::from_nanos(4)));
+        assert_eq!(histogram.quantile(0.7), Some(Duration::from_nanos(1024)));
+        assert_eq!(
+            histogram.quantile(1.0),
+            Some(Duration::from_nanos(u64::MAX))
+        );
+    }
+
+    /// Check that read interval statistics are computed correctly
+    #[test]
+    fn intervals() {
+        let mut stats = IntervalStats::default();
+        assert_eq!(stats.count(), 0);
+        assert_eq!(stats.min(), None);
+        assert_eq!(stats.max(), None);
+        assert_eq!(stats.mean(), None);
+        assert_eq!(stats.jitter(), None);
+
+        for micros in [2, 4, 4, 4, 5, 5, 7, 9] {
+            stats.record(Duration::from_micros(micros));
+        }
+        assert_eq!(stats.count(), 8);
+        assert_eq!(stats.min(), Some(Duration::from_micros(2)));
+        assert_eq!(stats.max(), Some(Duration::from_micros(9)));
+        assert_eq!(stats.mean(), Some(Duration::from_micros(5)));
+        assert_eq!(stats.jitter(), Some(Duration::from_micros(2)));
+    }
+}
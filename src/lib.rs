@@ -70,18 +70,106 @@
 //! // Post-process the output value before use
 //! output_mut.push_str("world!");
 //! ```
+//!
+//! If the type of the data can change at runtime, for example when a plugin
+//! reconfigures its output format, you can use a boxed trait object as the
+//! payload type. Since trait objects cannot be cloned, the initial buffers are
+//! then generated by a function:
+//!
+//! ```
+//! use std::any::Any;
+//! use triple_buffer::TripleBuffer;
+//!
+//! // Trait implemented by every data format that the producer can emit
+//! trait Frame: Any + Send {
+//!     fn format(&self) -> &'static str;
+//!     fn as_any(&self) -> &dyn Any;
+//! }
+//!
+//! struct Mono(f32);
+//! impl Frame for Mono {
+//!     fn format(&self) -> &'static str { "mono" }
+//!     fn as_any(&self) -> &dyn Any { self }
+//! }
+//!
+//! struct Stereo(f32, f32);
+//! impl Frame for Stereo {
+//!     fn format(&self) -> &'static str { "stereo" }
+//!     fn as_any(&self) -> &dyn Any { self }
+//! }
+//!
+//! let (mut buf_input, mut buf_output) =
+//!     TripleBuffer::from_fn(|| Box::new(Mono(0.0)) as Box<dyn Frame>).split();
+//!
+//! // The producer can switch formats from one write to the next...
+//! buf_input.write(Box::new(Stereo(0.5, -0.5)));
+//!
+//! // ...and the consumer inspects the format of each frame that it reads
+//! let frame = buf_output.read();
+//! assert_eq!(frame.format(), "stereo");
+//! let stereo = frame.as_any().downcast_ref::<Stereo>().unwrap();
+//! assert_eq!(stereo.1, -0.5);
+//! ```
 
-#![cfg_attr(not(test), no_std)]
+#![cfg_attr(not(any(test, feature = "std")), no_std)]
 #![deny(missing_debug_implementations, missing_docs)]
 
 extern crate alloc;
 
+mod atomic;
+#[cfg(feature = "fuzz-delays")]
+mod delay;
+#[cfg(feature = "std")]
+mod io;
+#[cfg(feature = "latency-stats")]
+mod latency;
+mod local;
+#[cfg(test)]
+mod schedule;
+#[cfg(feature = "serde")]
+mod serde_impls;
+#[cfg(target_has_atomic = "64")]
+mod small;
+
+#[cfg(feature = "std")]
+pub use io::OutputReader;
+#[cfg(feature = "latency-stats")]
+pub use latency::{IntervalStats, LatencyHistogram};
+pub use local::{LocalInput, LocalOutput, LocalTripleBuffer};
+#[cfg(target_has_atomic = "64")]
+pub use small::{AtomicInput, AtomicOutput, AtomicPayload, AtomicTripleBuffer};
+
+use crate::atomic::{BackInfoAtomic, CoreAtomic};
 use crossbeam_utils::CachePadded;
 
-use alloc::sync::Arc;
+use alloc::{borrow::Cow, boxed::Box, sync::Arc};
+#[cfg(feature = "stats")]
+use core::sync::atomic::AtomicUsize;
 use core::{
     cell::UnsafeCell,
-    sync::atomic::{AtomicU8, Ordering},
+    convert::TryFrom,
+    mem::ManuallyDrop,
+    sync::atomic::{AtomicBool, Ordering},
+};
+#[cfg(feature = "test-util")]
+use std::sync::Barrier;
+#[cfg(feature = "blocking")]
+use std::thread::Thread;
+#[cfg(feature = "async")]
+use std::{
+    future::Future,
+    pin::Pin,
+    task::{Context, Waker},
+};
+#[cfg(any(feature = "async", feature = "blocking"))]
+use std::{
+    sync::{Mutex, PoisonError},
+    task::Poll,
+};
+#[cfg(feature = "std")]
+use std::{
+    thread,
+    time::{Duration, Instant},
 };
 
 /// A triple buffer, useful for nonblocking and thread-safe data sharing
@@ -105,6 +193,77 @@ impl<T: Clone + Send> TripleBuffer<T> {
     pub fn new(initial: &T) -> Self {
         Self::new_impl(|| initial.clone())
     }
+
+    /// Construct a triple buffer with a pending update
+    ///
+    /// The consumer will initially see `first_value` as a fresh update from
+    /// the producer, as if it had been written using `Input::write()`, while
+    /// the other buffers are filled with clones of `placeholder`. This is
+    /// useful to bring up pipelines of triple buffers with valid initial data
+    /// flowing through them, without waiting for each producer to run once.
+    ///
+    pub fn new_seeded(placeholder: &T, first_value: T) -> Self {
+        let mut buf = Self::new(placeholder);
+        buf.input.write(first_value);
+        buf
+    }
+
+    /// Reset the triple buffer to the state of `TripleBuffer::new(initial)`
+    ///
+    /// This lets you reuse a triple buffer across multiple runs of a pipeline,
+    /// without going through a new memory allocation. The buffers are reset
+    /// using `Clone::clone_from()`, which lets types such as `Vec` or `String`
+    /// reuse their existing allocations as well.
+    ///
+    pub fn reinitialize(&mut self, initial: &T) {
+        // Reset the contents of the buffers. This is safe because we hold both
+        // the Input and the Output, which are the only way to access the shared
+        // state, by exclusive reference.
+        self.assert_paired();
+        for buffer in self.input.shared.buffers.iter() {
+            unsafe {
+                (*buffer.data.get()).clone_from(initial);
+                *buffer.sequence.get() = 0;
+                #[cfg(feature = "latency-stats")]
+                {
+                    *buffer.published_at.get() = None;
+                }
+            }
+        }
+
+        // Reset the buffer indices and sequence numbers
+        self.input
+            .shared
+            .back_info
+            .store(Self::INITIAL_BACK_INFO, Ordering::Relaxed);
+        self.input.input_idx = Self::INITIAL_INPUT_IDX;
+        self.input.last_sequence = 0;
+        #[cfg(feature = "std")]
+        {
+            self.input.coalesced_pending = false;
+        }
+        #[cfg(feature = "blocking")]
+        {
+            self.input.overwrite_streak = 0;
+        }
+        #[cfg(feature = "stats")]
+        {
+            self.input.shared.reads.store(0, Ordering::Relaxed);
+            self.input.reads_at_last_write = 0;
+            self.input.bytes_published = 0;
+        }
+        self.output.output_idx = Self::INITIAL_OUTPUT_IDX;
+        #[cfg(feature = "latency-stats")]
+        {
+            self.output.latency_histogram = LatencyHistogram::new();
+            self.output.last_read = None;
+            self.output.read_intervals = IntervalStats::default();
+        }
+        #[cfg(debug_assertions)]
+        if let Some(order_check) = &mut self.output.order_check {
+            order_check.previous = None;
+        }
+    }
 }
 //
 impl<T: Default + Send> Default for TripleBuffer<T> {
@@ -114,25 +273,320 @@ impl<T: Default + Send> Default for TripleBuffer<T> {
     }
 }
 //
+impl<T: Send> From<TripleBuffer<T>> for (Input<T>, Output<T>) {
+    /// Split a triple buffer into its input and output
+    ///
+    /// This is equivalent to `TripleBuffer::split()`, for use in generic code:
+    ///
+    /// ```
+    /// # use triple_buffer::{Input, Output, TripleBuffer};
+    /// let (mut input, mut output): (Input<_>, Output<_>) = TripleBuffer::new(&0).into();
+    /// input.write(42);
+    /// assert_eq!(*output.read(), 42);
+    /// ```
+    ///
+    fn from(buf: TripleBuffer<T>) -> Self {
+        buf.split()
+    }
+}
+//
 impl<T: Send> TripleBuffer<T> {
+    /// Initial back-buffer information of a fresh triple buffer
+    const INITIAL_BACK_INFO: BackBufferInfo = BackBufferInfo::clean(BufferIndex::from_const(0));
+
+    /// Initial input buffer index of a fresh triple buffer
+    const INITIAL_INPUT_IDX: BufferIndex = BufferIndex::from_const(1);
+
+    /// Initial output buffer index of a fresh triple buffer
+    const INITIAL_OUTPUT_IDX: BufferIndex = BufferIndex::from_const(2);
+
+    /// Construct a triple buffer, using a function to generate initial values
+    ///
+    /// The function is called once per internal buffer. Compared with `new()`,
+    /// this lets you avoid cloning an initial value, which matters when such
+    /// clones are expensive or do not have the properties that you want.
+    ///
+    /// For example, cloning a pre-sized collection like `vec![0.0; n]` zeroes
+    /// out `n` elements for each buffer, which is wasteful if the producer is
+    /// going to overwrite them anyway. And cloning an empty collection that was
+    /// built with `Vec::with_capacity(n)` does not preserve its capacity. Both
+    /// problems are avoided by generating each buffer separately:
+    ///
+    /// ```
+    /// # use triple_buffer::TripleBuffer;
+    /// let (mut input, _output) = TripleBuffer::from_fn(|| Vec::<f32>::with_capacity(1024)).split();
+    /// let buffer = input.input_buffer();
+    /// assert!(buffer.is_empty());
+    /// assert!(buffer.capacity() >= 1024);
+    /// ```
+    ///
+    /// Since all buffers are initially empty, consumers will observe an empty
+    /// collection until the producer has written something in there.
+    ///
+    pub fn from_fn(generator: impl FnMut() -> T) -> Self {
+        Self::new_impl(generator)
+    }
+
+    /// Construct a triple buffer from a boxed slice of exactly three values
+    ///
+    /// The first value is the one that the consumer will initially observe.
+    /// The other two fill the remaining buffers, which the producer will get
+    /// as input buffers when writing in place. If the slice does not contain
+    /// exactly three values, it is handed back to you in the `Err` variant.
+    ///
+    /// This is useful when the initial buffer values are produced dynamically,
+    /// for example as a `Vec` loaded from a configuration file.
+    ///
+    pub fn from_slice_exact(values: Box<[T]>) -> Result<Self, Box<[T]>> {
+        let values = <Box<[T; 3]>>::try_from(values)?;
+        let [output, input, back] = *values;
+        let mut values = IntoIterator::into_iter([back, input, output]);
+        Ok(Self::new_impl(|| {
+            values.next().expect("Should be called thrice")
+        }))
+    }
+
     /// Construct a triple buffer, using a functor to generate initial values
     fn new_impl(mut generator: impl FnMut() -> T) -> Self {
         // Start with the shared state...
-        let shared_state = Arc::new(SharedState::new(|_i| generator(), 0));
+        let shared_state = Arc::new(SharedState::new(|_i| generator(), Self::INITIAL_BACK_INFO));
 
         // ...then construct the input and output structs
         TripleBuffer {
             input: Input {
                 shared: shared_state.clone(),
-                input_idx: 1,
+                input_idx: Self::INITIAL_INPUT_IDX,
+                last_sequence: 0,
+                forbid_overwrites: false,
+                #[cfg(feature = "std")]
+                last_coalesced_publish: None,
+                #[cfg(feature = "std")]
+                coalesced_pending: false,
+                #[cfg(feature = "blocking")]
+                overwrite_streak: 0,
+                #[cfg(feature = "stats")]
+                reads_at_last_write: 0,
+                #[cfg(feature = "stats")]
+                bytes_published: 0,
+                #[cfg(feature = "stats")]
+                byte_len: |_| core::mem::size_of::<T>(),
             },
             output: Output {
                 shared: shared_state,
-                output_idx: 2,
+                output_idx: Self::INITIAL_OUTPUT_IDX,
+                #[cfg(feature = "latency-stats")]
+                latency_histogram: LatencyHistogram::new(),
+                #[cfg(feature = "latency-stats")]
+                last_read: None,
+                #[cfg(feature = "latency-stats")]
+                read_intervals: IntervalStats::default(),
+                #[cfg(debug_assertions)]
+                order_check: None,
             },
         }
     }
 
+    /// Apply an in-place operation to each of the triple buffer's buffers
+    ///
+    /// Before the triple buffer is split, you have exclusive access to all of
+    /// its internal buffers, which makes this a good time to prepare them for
+    /// later use. For example, if you are sending collections like `Vec`, you
+    /// can reserve enough capacity in every buffer so that in-place updates
+    /// performed by the producer later on never need to allocate memory:
+    ///
+    /// ```
+    /// # use triple_buffer::TripleBuffer;
+    /// let mut buf = TripleBuffer::new(&Vec::<f32>::new());
+    /// buf.for_each_buffer_mut(|v| v.reserve(1024));
+    /// let (mut input, _output) = buf.split();
+    /// assert!(input.input_buffer().capacity() >= 1024);
+    /// ```
+    ///
+    /// Please keep in mind that the consumer may observe any of these buffers
+    /// before the producer has published anything, so you should not use this
+    /// method to make their values differ in observable ways.
+    ///
+    /// The producer cannot do this on its own after the triple buffer has been
+    /// split, because at any point in time, one of the buffers belongs to the
+    /// consumer and another one may be fetched by the consumer at any moment.
+    /// But if both halves are available, e.g. during a setup phase before a
+    /// burst of updates, you can reassemble the triple buffer to reserve
+    /// capacity in every buffer, then split it again:
+    ///
+    /// ```
+    /// # use triple_buffer::TripleBuffer;
+    /// # let (input, output) = TripleBuffer::new(&Vec::<f32>::new()).split();
+    /// let mut buf = TripleBuffer::reunite(input, output).unwrap();
+    /// buf.for_each_buffer_mut(|v| v.reserve(1024));
+    /// let (input, output) = buf.split();
+    /// # drop((input, output));
+    /// ```
+    ///
+    pub fn for_each_buffer_mut(&mut self, mut f: impl FnMut(&mut T)) {
+        // This is safe because we hold both the Input and the Output, which
+        // are the only way to access the shared state, by exclusive reference.
+        self.assert_paired();
+        for buffer in self.input.shared.buffers.iter() {
+            f(unsafe { &mut *buffer.data.get() });
+        }
+    }
+
+    /// Hand over the buffers to a callback when the triple buffer is destroyed
+    ///
+    /// By default, the values stored inside of the triple buffer are dropped
+    /// in place when both the `Input` and the `Output` have been dropped. If
+    /// these values hold resources that require explicit release, e.g. because
+    /// they must be returned to a pool, you can instead use this method to
+    /// register a callback that receives each of the three values by move.
+    ///
+    /// ```
+    /// # use std::sync::{Arc, Mutex};
+    /// # use triple_buffer::TripleBuffer;
+    /// let pool = Arc::new(Mutex::new(Vec::new()));
+    /// let mut buf = TripleBuffer::new(&vec![0u8; 1024]);
+    /// let pool2 = pool.clone();
+    /// buf.on_drop(move |v| pool2.lock().unwrap().push(v));
+    /// drop(buf.split());
+    /// assert_eq!(pool.lock().unwrap().len(), 3);
+    /// ```
+    ///
+    pub fn on_drop(&mut self, callback: impl FnMut(T) + Send + 'static) {
+        // This is safe because we hold both the Input and the Output, which
+        // are the only way to access the shared state, by exclusive reference.
+        self.assert_paired();
+        unsafe { *self.input.shared.on_drop.get() = Some(DropCallback(Box::new(callback))) };
+    }
+
+    /// Destroy the triple buffer, extracting the values stored inside of it
+    ///
+    /// This is an alternative to `on_drop()` for when teardown happens at a
+    /// well-known point, e.g. if you want to return the buffers' allocations
+    /// to a pool without setting up a callback that can be sent across
+    /// threads. The three values are returned in an unspecified order, and
+    /// the callback registered with `on_drop()`, if any, is not called.
+    ///
+    /// ```
+    /// # use triple_buffer::TripleBuffer;
+    /// let mut pool = Vec::new();
+    /// let mut buf = TripleBuffer::new(&vec![0u8; 1024]);
+    /// let (input, _output) = buf.split_mut();
+    /// input.write(vec![1, 2, 3]);
+    /// pool.extend(buf.into_inner());
+    /// assert_eq!(pool.len(), 3);
+    /// ```
+    ///
+    pub fn into_inner(self) -> [T; 3] {
+        // Once the Input and Output are gone, we own the shared state...
+        self.assert_paired();
+        let shared = self.input.shared.clone();
+        drop(self);
+        let Ok(mut shared) = Arc::try_unwrap(shared) else {
+            unreachable!("No one else can hold a reference to the shared state")
+        };
+
+        // ...so we can move the buffers out of it. This is safe because the
+        // buffers_taken flag prevents the Drop impl from doing so again.
+        shared.buffers_taken = true;
+        let buffers = unsafe { ManuallyDrop::take(&mut shared.buffers) };
+        buffers.map(|buffer| buffer.into_inner().data.into_inner())
+    }
+
+    /// Attach a label to the triple buffer, for diagnostics purposes
+    ///
+    /// When a program uses many triple buffers, this lets you tell them apart
+    /// in debug output. The label can be queried from both ends of the triple
+    /// buffer via `Input::label()` and `Output::label()`, and it appears in
+    /// their `Debug` output.
+    ///
+    /// ```
+    /// # use triple_buffer::TripleBuffer;
+    /// let mut buf = TripleBuffer::new(&0);
+    /// buf.set_label("camera frames");
+    /// let (input, output) = buf.split();
+    /// assert_eq!(input.label(), Some("camera frames"));
+    /// assert!(format!("{output:?}").contains("camera frames"));
+    /// ```
+    ///
+    pub fn set_label(&mut self, label: &'static str) {
+        // This is safe for the same reason as in on_drop()
+        self.assert_paired();
+        unsafe { self.input.shared.label.set(Some(label)) };
+    }
+
+    /// Register a function to be called every time an update is published
+    ///
+    /// This is meant for lightweight diagnostics in environments where no
+    /// logging or tracing infrastructure is available, e.g. toggling a GPIO
+    /// pin on a microcontroller so that publications can be observed with an
+    /// oscilloscope. The hook is called by the producer, right after the
+    /// update has been made available to the consumer. It should be quick,
+    /// since it is on the producer's hot path.
+    ///
+    /// When no hook is registered, all it costs is a check for its presence.
+    ///
+    pub fn on_publish(&mut self, hook: fn()) {
+        // This is safe for the same reason as in on_drop()
+        self.assert_paired();
+        unsafe { self.input.shared.on_publish.set(Some(hook)) };
+    }
+
+    /// Register a function to be called every time an update is fetched
+    ///
+    /// This is the consumer-side counterpart of `on_publish()`. The hook is
+    /// called by the consumer, right after it fetched a new update. Checking
+    /// for updates when there are none does not call it.
+    ///
+    /// ```
+    /// # use core::sync::atomic::{AtomicUsize, Ordering};
+    /// # use triple_buffer::TripleBuffer;
+    /// static CONSUMED: AtomicUsize = AtomicUsize::new(0);
+    /// let mut buf = TripleBuffer::new(&0);
+    /// buf.on_consume(|| {
+    ///     CONSUMED.fetch_add(1, Ordering::Relaxed);
+    /// });
+    /// let (mut input, mut output) = buf.split();
+    /// input.write(1);
+    /// output.read();
+    /// output.read();
+    /// assert_eq!(CONSUMED.load(Ordering::Relaxed), 1);
+    /// ```
+    ///
+    pub fn on_consume(&mut self, hook: fn()) {
+        // This is safe for the same reason as in on_drop()
+        self.assert_paired();
+        unsafe { self.input.shared.on_consume.set(Some(hook)) };
+    }
+
+    /// Check the internal invariants of the triple buffer
+    ///
+    /// This checks that the input, output and back-buffer indices are all in
+    /// range and distinct from each other, and that the back-buffer information
+    /// is well-formed. An error means that the internal state of the triple
+    /// buffer was corrupted, e.g. by unsafe code misbehaving elsewhere.
+    ///
+    pub fn validate(&self) -> Result<(), &'static str> {
+        let back_info = self.input.shared.back_info.load(Ordering::Relaxed);
+        if back_info.0 & !BackBufferInfo::VALID_BITS != 0 {
+            return Err("back-buffer information has unexpected bits set");
+        }
+        let back_idx = back_info.index();
+        let input_idx = self.input.input_idx;
+        let output_idx = self.output.output_idx;
+        if [input_idx, output_idx, back_idx]
+            .iter()
+            .any(|idx| BufferIndex::new(idx.0).is_none())
+        {
+            return Err("buffer index out of range");
+        }
+        if input_idx == output_idx || input_idx == back_idx || output_idx == back_idx {
+            return Err("buffer indices are not distinct");
+        }
+        if !Arc::ptr_eq(&self.input.shared, &self.output.shared) {
+            return Err("input and output do not share the same state");
+        }
+        Ok(())
+    }
+
     /// Extract input and output of the triple buffer
     //
     // NOTE: Although it would be nicer to directly return `Input` and `Output`
@@ -146,6 +600,139 @@ impl<T: Send> TripleBuffer<T> {
     pub fn split(self) -> (Input<T>, Output<T>) {
         (self.input, self.output)
     }
+
+    /// Reassemble a triple buffer from its input and output
+    ///
+    /// This undoes `split()`, which gives you access to methods that require
+    /// holding both ends of the triple buffer again, such as `reinitialize()`
+    /// or `into_inner()`. If the input and output do not belong to the same
+    /// triple buffer, they are handed back to you.
+    ///
+    /// ```
+    /// # use triple_buffer::TripleBuffer;
+    /// let (input, output) = TripleBuffer::new(&0).split();
+    /// let (other_input, other_output) = TripleBuffer::new(&0).split();
+    /// let (input, other_output) = TripleBuffer::reunite(input, other_output).unwrap_err();
+    /// assert!(TripleBuffer::reunite(input, output).is_ok());
+    /// # drop((other_input, other_output));
+    /// ```
+    ///
+    #[allow(clippy::result_large_err)]
+    pub fn reunite(input: Input<T>, output: Output<T>) -> Result<Self, (Input<T>, Output<T>)> {
+        if input.is_paired_with(&output) {
+            Ok(Self { input, output })
+        } else {
+            Err((input, output))
+        }
+    }
+
+    /// Access input and output of the triple buffer, without extracting them
+    ///
+    /// This is useful when both ends of the triple buffer are used from the
+    /// same place, e.g. in single-threaded code or in tests, and you would
+    /// rather keep the `TripleBuffer` as the single owner of both.
+    ///
+    /// If you swap the returned halves with those of another triple buffer,
+    /// methods that need exclusive access to the whole triple buffer, such as
+    /// `reinitialize()`, will panic.
+    ///
+    /// ```
+    /// # use triple_buffer::TripleBuffer;
+    /// let mut buf = TripleBuffer::new(&0);
+    /// let (input, output) = buf.split_mut();
+    /// input.write(42);
+    /// assert_eq!(*output.read(), 42);
+    /// ```
+    ///
+    pub fn split_mut(&mut self) -> (&mut Input<T>, &mut Output<T>) {
+        (&mut self.input, &mut self.output)
+    }
+}
+//
+#[cfg(feature = "std")]
+impl<T: Send + 'static> TripleBuffer<T> {
+    /// Split the triple buffer and run its producer and consumer on two
+    /// dedicated threads
+    ///
+    /// This takes care of the usual boilerplate of constructing a triple
+    /// buffer, splitting it, and moving each half to its own thread. Threads
+    /// are named after the label of the triple buffer (see `set_label()`) if
+    /// there is one, which makes them easier to tell apart in debuggers and
+    /// profilers.
+    ///
+    /// ```
+    /// # use triple_buffer::TripleBuffer;
+    /// let (producer, consumer) = TripleBuffer::new(&0).spawn(
+    ///     |mut input| input.write(42),
+    ///     |mut output| {
+    ///         while !output.updated() {
+    ///             std::thread::yield_now();
+    ///         }
+    ///         *output.read()
+    ///     },
+    /// )?;
+    /// producer.join().unwrap();
+    /// assert_eq!(consumer.join().unwrap(), 42);
+    /// # Ok::<(), std::io::Error>(())
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Like `std::thread::Builder::spawn()`, this fails if the OS cannot
+    /// create a thread. If this happens when spawning the consumer thread, the
+    /// producer thread has already started and keeps running detached, since
+    /// Rust threads cannot be cancelled. The consumer's `Output` is dropped,
+    /// so the producer can notice that nobody is listening anymore through
+    /// `Input::reader_alive()`.
+    ///
+    pub fn spawn<P, C>(
+        self,
+        producer: impl FnOnce(Input<T>) -> P + Send + 'static,
+        consumer: impl FnOnce(Output<T>) -> C + Send + 'static,
+    ) -> std::io::Result<(thread::JoinHandle<P>, thread::JoinHandle<C>)>
+    where
+        P: Send + 'static,
+        C: Send + 'static,
+    {
+        let label = self.input.label().unwrap_or("triple buffer");
+        let (input, output) = self.split();
+        let producer = thread::Builder::new()
+            .name(format!("{label} producer"))
+            .spawn(move || producer(input))?;
+        let consumer = thread::Builder::new()
+            .name(format!("{label} consumer"))
+            .spawn(move || consumer(output))?;
+        Ok((producer, consumer))
+    }
+}
+//
+// Internal interface
+impl<T: Send> TripleBuffer<T> {
+    /// Check that our Input and Output share the same state
+    ///
+    /// Methods which modify the shared state through a shared reference rely
+    /// on the fact that we hold both ends of the triple buffer, so no one else
+    /// can access it. But `split_mut()` lets users swap our Input or Output
+    /// with that of another triple buffer, so this must be checked.
+    ///
+    fn assert_paired(&self) {
+        assert!(
+            Arc::ptr_eq(&self.input.shared, &self.output.shared),
+            "Input and Output of this TripleBuffer were swapped with other ones"
+        );
+    }
+}
+//
+/// Check a memory ordering passed to one of the `_with_ordering()` methods,
+/// see `Input::publish_with_ordering()` for the rules
+#[track_caller]
+fn swap_ordering(order: Ordering) -> Ordering {
+    assert!(
+        matches!(order, Ordering::AcqRel | Ordering::SeqCst),
+        "Triple buffer swaps must use AcqRel or SeqCst ordering, not {:?}",
+        order
+    );
+    order
 }
 //
 /// Shorthand for `TripleBuffer::new(initial).split()`
@@ -168,10 +755,32 @@ impl<T: Clone + Send> Clone for TripleBuffer<T> {
             input: Input {
                 shared: shared_state.clone(),
                 input_idx: self.input.input_idx,
+                last_sequence: self.input.last_sequence,
+                forbid_overwrites: self.input.forbid_overwrites,
+                #[cfg(feature = "std")]
+                last_coalesced_publish: self.input.last_coalesced_publish,
+                #[cfg(feature = "std")]
+                coalesced_pending: self.input.coalesced_pending,
+                #[cfg(feature = "blocking")]
+                overwrite_streak: self.input.overwrite_streak,
+                #[cfg(feature = "stats")]
+                reads_at_last_write: self.input.reads_at_last_write,
+                #[cfg(feature = "stats")]
+                bytes_published: self.input.bytes_published,
+                #[cfg(feature = "stats")]
+                byte_len: self.input.byte_len,
             },
             output: Output {
                 shared: shared_state,
                 output_idx: self.output.output_idx,
+                #[cfg(feature = "latency-stats")]
+                latency_histogram: self.output.latency_histogram.clone(),
+                #[cfg(feature = "latency-stats")]
+                last_read: self.output.last_read,
+                #[cfg(feature = "latency-stats")]
+                read_intervals: self.output.read_intervals,
+                #[cfg(debug_assertions)]
+                order_check: None,
             },
         }
     }
@@ -187,6 +796,7 @@ impl<T: PartialEq + Send> PartialEq for TripleBuffer<T> {
         // Compare the rest of the triple buffer states
         shared_states_equal
             && (self.input.input_idx == other.input.input_idx)
+            && (self.input.last_sequence == other.input.last_sequence)
             && (self.output.output_idx == other.output.output_idx)
     }
 }
@@ -198,6 +808,10 @@ impl<T: PartialEq + Send> PartialEq for TripleBuffer<T> {
 /// the producer and the consumer will result in cache contention, but deadlocks
 /// and scheduling-induced slowdowns cannot happen.
 ///
+/// The only exception is when the consumer is blocked waiting for an update,
+/// in `Output::changed()` or `Output::wait_and_read()`. In that case, the
+/// publish that ends the wait briefly locks a mutex to wake the consumer up.
+///
 #[derive(Debug)]
 pub struct Input<T: Send> {
     /// Reference-counted shared state
@@ -205,11 +819,55 @@ pub struct Input<T: Send> {
 
     /// Index of the input buffer (which is private to the producer)
     input_idx: BufferIndex,
+
+    /// Sequence number of the last published update
+    last_sequence: u64,
+
+    /// Truth that overwriting unread data should be treated as a bug
+    forbid_overwrites: bool,
+
+    /// Time at which `write_coalesced()` last published an update
+    #[cfg(feature = "std")]
+    last_coalesced_publish: Option<Instant>,
+
+    /// Truth that `write_coalesced()` left a value unpublished
+    #[cfg(feature = "std")]
+    coalesced_pending: bool,
+
+    /// Number of consecutive updates that overwrote unread data
+    #[cfg(feature = "blocking")]
+    overwrite_streak: u32,
+
+    /// Value of the consumer's read counter when we last published an update
+    #[cfg(feature = "stats")]
+    reads_at_last_write: usize,
+
+    /// Total size of the updates published so far, in bytes
+    #[cfg(feature = "stats")]
+    bytes_published: u64,
+
+    /// Function used to measure the size of an update, in bytes
+    #[cfg(feature = "stats")]
+    byte_len: fn(&T) -> usize,
 }
 //
 // Public interface
 impl<T: Send> Input<T> {
+    /// Construct a placeholder input that is not connected to any consumer
+    ///
+    /// This can be used to initialize a struct field before the actual triple
+    /// buffer is available, and replace it with a connected input later on.
+    /// Values written to a disconnected input are never observed by anyone.
+    ///
+    pub fn disconnected() -> Self
+    where
+        T: Default,
+    {
+        TripleBuffer::default().split().0
+    }
+
     /// Write a new value into the triple buffer
+    #[track_caller]
     pub fn write(&mut self, value: T) {
         // Update the input buffer
         *self.input_buffer() = value;
@@ -218,486 +876,3644 @@ impl<T: Send> Input<T> {
         self.publish();
     }
 
-    /// Check if the consumer has fetched our last submission yet
+    /// Write a copy of a value into the triple buffer
     ///
-    /// This method is only intended for diagnostics purposes. Please do not let
-    /// it inform your decision of sending or not sending a value, as that would
-    /// effectively be building a very poor spinlock-based double buffer
-    /// implementation. If what you truly need is a double buffer, build
-    /// yourself a proper blocking one instead of wasting CPU time.
+    /// This is useful when you want to keep using the value after sending it,
+    /// as it avoids cloning the value only to move the clone into the buffer.
+    /// The copy is made using `Clone::clone_from()`, which lets types such as
+    /// `Vec` or `String` reuse the existing allocation of the input buffer.
     ///
-    pub fn consumed(&self) -> bool {
-        let back_info = self.shared.back_info.load(Ordering::Relaxed);
-        back_info & BACK_DIRTY_BIT == 0
+    /// This is also the right tool when successive updates only change some
+    /// fields of a struct, and the other fields must keep their latest value.
+    /// Modifying the input buffer in place is not enough for this purpose, as
+    /// it holds whichever value the triple buffer recycled, which may be older
+    /// than your last few updates. Instead, keep the up-to-date value on the
+    /// producer side, modify the fields that changed, and write a copy of it:
+    ///
+    /// ```
+    /// # use triple_buffer::triple_buffer;
+    /// #[derive(Clone, Default)]
+    /// struct Telemetry {
+    ///     position: (f32, f32),
+    ///     battery: f32,
+    /// }
+    ///
+    /// let mut state = Telemetry::default();
+    /// let (mut input, mut output) = triple_buffer(&state);
+    ///
+    /// state.position = (1.0, 2.0);
+    /// input.write_ref(&state);
+    /// state.battery = 0.9;
+    /// input.write_ref(&state);
+    ///
+    /// let latest = output.read();
+    /// assert_eq!((latest.position, latest.battery), ((1.0, 2.0), 0.9));
+    /// ```
+    ///
+    #[track_caller]
+    pub fn write_ref(&mut self, value: &T)
+    where
+        T: Clone,
+    {
+        // Update the input buffer
+        self.input_buffer().clone_from(value);
+
+        // Publish our update to the consumer
+        self.publish();
     }
 
-    /// Access the input buffer directly
+    /// Write a new value into the triple buffer, then access the new input buffer
     ///
-    /// This advanced interface allows you to update the input buffer in place,
-    /// so that you can avoid creating values of type T repeatedy just to push
-    /// them into the triple buffer when doing so is expensive.
+    /// After publishing, the producer gets a new input buffer, which it
+    /// reclaimed from the back-buffer. This method gives you access to that
+    /// buffer right away, so that you can reset it or extract its old contents
+    /// as soon as you are done publishing, rather than before the next write.
     ///
-    /// However, by using it, you force yourself to take into account some
-    /// implementation subtleties that you could normally ignore.
+    /// The same caveats as for `input_buffer()` apply: the reclaimed buffer may
+    /// contain any value that went through the triple buffer in the past, or a
+    /// value that was modified by the consumer.
     ///
-    /// First, the buffer does not contain the last value that you published
-    /// (which is now available to the consumer thread). In fact, what you get
-    /// may not match _any_ value that you sent in the past, but rather be a new
-    /// value that was written in there by the consumer thread. All you can
-    /// safely assume is that the buffer contains a valid value of type T, which
-    /// you may need to "clean up" before use using a type-specific process.
+    #[track_caller]
+    pub fn write_reclaiming(&mut self, value: T) -> &mut T {
+        self.write(value);
+        self.input_buffer()
+    }
+
+    /// Write a new value into the triple buffer, getting back the value that
+    /// it overwrote, if any
     ///
-    /// Second, we do not send updates automatically. You need to call
-    /// `publish()` in order to propagate a buffer update to the consumer.
-    /// Alternative designs based on Drop were considered, but considered too
-    /// magical for the target audience of this interface.
+    /// With `write()`, if the consumer did not read the previous update yet,
+    /// that update is silently dropped. This method returns it instead, which
+    /// lets you reuse its allocation if it is heap-backed.
     ///
-    pub fn input_buffer(&mut self) -> &mut T {
-        // This is safe because the synchronization protocol ensures that we
-        // have exclusive access to this buffer.
-        let input_ptr = self.shared.buffers[self.input_idx as usize].get();
-        unsafe { &mut *input_ptr }
+    /// ```
+    /// # use triple_buffer::triple_buffer;
+    /// let (mut input, mut output) = triple_buffer(&Vec::<u8>::new());
+    /// assert_eq!(input.write_and_reclaim(vec![1]), None);
+    /// assert_eq!(input.write_and_reclaim(vec![2]), Some(vec![1]));
+    /// assert_eq!(*output.read(), [2]);
+    /// ```
+    ///
+    /// If the consumer did read the previous update, `None` is returned. The
+    /// consumer may still be using that value as its output buffer, so it is
+    /// not ours to take.
+    ///
+    #[track_caller]
+    pub fn write_and_reclaim(&mut self, value: T) -> Option<T> {
+        // Put the new value in the input buffer, keeping its stale contents...
+        let stale = core::mem::replace(self.input_buffer(), value);
+
+        // ...and if publishing overwrote an unread update, which then becomes
+        // our input buffer, put these stale contents in its place.
+        if self.publish() {
+            Some(core::mem::replace(self.input_buffer(), stale))
+        } else {
+            None
+        }
     }
 
-    /// Publish the current input buffer, checking for overwrites
+    /// Build a new value in place inside of the input buffer, then publish it
     ///
-    /// After updating the input buffer using `input_buffer()`, you can use this
-    /// method to publish your updates to the consumer.
+    /// This is a shorthand for modifying `input_buffer()`, then calling
+    /// `publish()`. It lets you reuse the input buffer's resources, e.g. to
+    /// fill a `Vec` without allocating memory:
     ///
-    /// This will replace the current input buffer with another one, as you
-    /// cannot continue using the old one while the consumer is accessing it.
+    /// ```
+    /// # use triple_buffer::triple_buffer;
+    /// let (mut input, mut output) = triple_buffer(&vec![0.0; 16]);
+    /// input.write_with(|samples| {
+    ///     samples.clear();
+    ///     samples.extend((0..16).map(|i| i as f32));
+    /// });
+    /// assert_eq!(output.read().len(), 16);
+    /// ```
     ///
-    /// It will also tell you whether you overwrote a value which was not read
-    /// by the consumer thread.
+    /// The same caveats as for `input_buffer()` apply: the buffer may contain
+    /// any value that went through the triple buffer in the past, which is not
+    /// necessarily your last update. So `f` should overwrite all of it. If you
+    /// need to carry state forward from one update to the next, e.g. to apply
+    /// a diff to the previous value, keep that value on the producer side and
+    /// publish copies of it with `write_ref()` instead.
     ///
-    pub fn publish(&mut self) -> bool {
-        // Swap the input buffer and the back buffer, setting the dirty bit
-        //
-        // The ordering must be AcqRel, because...
-        //
-        // - Our accesses to the old buffer must not be reordered after this
-        //   operation (which mandates Release ordering), otherwise they could
-        //   race with the consumer accessing the freshly published buffer.
-        // - Our accesses from the buffer must not be reordered before this
-        //   operation (which mandates Consume ordering, that is best
-        //   approximated by Acquire in Rust), otherwise they would race with
-        //   the consumer accessing the buffer as well before switching to
-        //   another buffer.
-        //   * This reordering may seem paradoxical, but could happen if the
-        //     compiler or CPU correctly speculated the new buffer's index
-        //     before that index is actually read, as well as on weird hardware
-        //     with incoherent caches like GPUs or old DEC Alpha where keeping
-        //     data in sync across cores requires manual action.
-        //
-        let former_back_info = self
-            .shared
-            .back_info
-            .swap(self.input_idx | BACK_DIRTY_BIT, Ordering::AcqRel);
+    #[track_caller]
+    pub fn write_with(&mut self, f: impl FnOnce(&mut T)) {
+        f(self.input_buffer());
+        self.publish();
+    }
 
-        // The old back buffer becomes our new input buffer
-        self.input_idx = former_back_info & BACK_INDEX_MASK;
+    /// Write a new value into the triple buffer, limiting the publication rate
+    ///
+    /// The value is always written into the input buffer, but it is only
+    /// published if at least `window` has elapsed since the last time this
+    /// method published an update. Otherwise, the value stays in the input
+    /// buffer, where it will be overwritten by the next call.
+    ///
+    /// This is useful for producers which generate updates much faster than
+    /// the consumer can use them, as it reduces the amount of traffic going
+    /// through the triple buffer. Keep in mind that the last value that you
+    /// send may remain unpublished until you call `flush()` or `publish()`.
+    ///
+    /// Returns whether the value was published.
+    ///
+    #[cfg(feature = "std")]
+    #[track_caller]
+    pub fn write_coalesced(&mut self, value: T, window: Duration) -> bool {
+        // Update the input buffer
+        *self.input_buffer() = value;
 
-        // Tell whether we have overwritten unread data
-        former_back_info & BACK_DIRTY_BIT != 0
+        // Publish our update if the time window has elapsed
+        let now = Instant::now();
+        match self.last_coalesced_publish {
+            Some(last_publish) if now.duration_since(last_publish) < window => {
+                self.coalesced_pending = true;
+                false
+            }
+            _ => {
+                self.publish();
+                self.last_coalesced_publish = Some(now);
+                true
+            }
+        }
     }
-}
 
-/// Consumer interface to the triple buffer
-///
-/// The consumer of data can use this struct to access the latest published
-/// update from the producer whenever he likes. Readout is nonblocking: a
-/// collision between the producer and consumer will result in cache contention,
-/// but deadlocks and scheduling-induced slowdowns cannot happen.
-///
-#[derive(Debug)]
-pub struct Output<T: Send> {
-    /// Reference-counted shared state
-    shared: Arc<SharedState<T>>,
+    /// Publish the value left in the input buffer by `write_coalesced()`, if any
+    ///
+    /// A value is pending if the last call to `write_coalesced()` did not
+    /// publish it, and nothing was published since. Values that you write into
+    /// `input_buffer()` yourself are never considered pending, since there is
+    /// no way to tell whether you are done modifying them.
+    ///
+    /// Call this once you are done writing, e.g. at the end of a batch of
+    /// updates or before the producer goes away, to make sure that the last
+    /// value is not lost. Returns whether a value was published.
+    ///
+    #[cfg(feature = "std")]
+    #[track_caller]
+    pub fn flush(&mut self) -> bool {
+        if self.coalesced_pending {
+            self.publish();
+            true
+        } else {
+            false
+        }
+    }
 
-    /// Index of the output buffer (which is private to the consumer)
-    output_idx: BufferIndex,
-}
-//
-// Public interface
-impl<T: Send> Output<T> {
-    /// Access the latest value from the triple buffer
-    pub fn read(&mut self) -> &T {
-        // Fetch updates from the producer
-        self.update();
+    /// Compute and write a new value, unless the consumer is lagging behind
+    ///
+    /// If the consumer did not fetch the last update yet, a new value would
+    /// just overwrite it, so this method returns `false` without calling
+    /// `make_value`. Otherwise, the value is computed, published, and this
+    /// method returns `true`. This lets you skip expensive computations when
+    /// the consumer cannot keep up with them.
+    ///
+    /// This check is inherently racy: the consumer may fetch the last update
+    /// right after we decided to skip the computation, or the value may end up
+    /// overwritten anyway if we take a long time to compute it. So you should
+    /// only use this method as a best-effort optimization. It also means that
+    /// if the consumer stops reading, no more values will be produced.
+    ///
+    #[track_caller]
+    pub fn write_lazy(&mut self, make_value: impl FnOnce() -> T) -> bool {
+        // Skip the computation if the last update was not consumed
+        if !self.consumed() {
+            return false;
+        }
 
-        // Give access to the output buffer
-        self.output_buffer()
+        // Otherwise, compute and publish the new value
+        self.write(make_value());
+        true
     }
 
-    /// Tell whether a buffer update is incoming from the producer
+    /// Write a new value, blocking if too many updates went unread in a row
+    ///
+    /// This is a middle ground between the lossy behavior of `write()` and a
+    /// fully blocking channel. Up to `max_consecutive_overwrites` updates in a
+    /// row may overwrite data that the consumer did not read. Past this
+    /// point, this method blocks until the consumer fetches the last update,
+    /// before publishing the new one. The streak of overwrites is reset
+    /// whenever an update is published without overwriting unread data.
+    ///
+    /// With `max_consecutive_overwrites == 0`, no update is ever lost, which
+    /// turns the triple buffer into a blocking channel of capacity 1.
+    ///
+    /// While blocked, the producer thread is parked, and the consumer wakes it
+    /// up when it fetches the update that the producer is waiting for. If the
+    /// consumer is dropped, the producer does not block anymore, as there is
+    /// nobody left to wait for.
+    ///
+    /// # Deadlocks
+    ///
+    /// The producer only resumes once the consumer fetches an update. If the
+    /// consumer runs on the same thread as the producer, e.g. because both
+    /// halves of the triple buffer are driven by a single event loop, this
+    /// cannot happen once the overwrite budget is spent, and this method
+    /// blocks forever. Use `write_bounded_loss_timeout()` if you cannot rule
+    /// this out.
+    ///
+    #[cfg(feature = "blocking")]
+    #[track_caller]
+    pub fn write_bounded_loss(&mut self, value: T, max_consecutive_overwrites: u32) {
+        // Update the input buffer
+        *self.input_buffer() = value;
+
+        // If overwriting unread data now would exceed our budget, wait for the
+        // consumer to catch up
+        if self.overwrite_streak >= max_consecutive_overwrites {
+            self.wait_for_consumer(None);
+        }
+
+        // Publish our update, keeping track of overwrites
+        self.publish_bounded_loss();
+    }
+
+    /// Like `write_bounded_loss()`, but give up waiting after some time
+    ///
+    /// If the overwrite budget is spent and the consumer does not fetch the
+    /// last update within `timeout`, nothing is published and the value is
+    /// handed back as an error. The overwrite streak is left unchanged, so
+    /// the next bounded-loss write will wait for the consumer again.
+    ///
+    #[cfg(feature = "blocking")]
+    #[track_caller]
+    pub fn write_bounded_loss_timeout(
+        &mut self,
+        value: T,
+        max_consecutive_overwrites: u32,
+        timeout: Duration,
+    ) -> Result<(), T> {
+        // If overwriting unread data now would exceed our budget, wait for the
+        // consumer to catch up, but no longer than allowed
+        if self.overwrite_streak >= max_consecutive_overwrites
+            && !self.wait_for_consumer(Some(Instant::now() + timeout))
+        {
+            return Err(value);
+        }
+
+        // Publish our update, keeping track of overwrites
+        *self.input_buffer() = value;
+        self.publish_bounded_loss();
+        Ok(())
+    }
+
+    /// Write a new value into the triple buffer, and track its consumption
+    ///
+    /// This returns a future which resolves once the consumer has fetched the
+    /// value, which is useful in async code that must occasionally wait for a
+    /// specific value to be picked up, e.g. to release a resource associated
+    /// with it. The future resolves to `true` if the value was fetched, and to
+    /// `false` if the consumer went away without fetching it.
+    ///
+    /// The future borrows the `Input`, so no other value can be published
+    /// while it is pending, and thus the tracked value cannot be overwritten.
+    /// Dropping the future stops the tracking without affecting the value.
+    ///
+    /// Tracking has some overhead, as it requires the consumer to wake up the
+    /// producer, so this should not be used for every write.
+    ///
+    #[cfg(feature = "async")]
+    #[track_caller]
+    pub fn write_tracked(&mut self, value: T) -> ConsumedFuture<'_, T> {
+        self.write(value);
+        ConsumedFuture { input: self }
+    }
+
+    /// Check if the consumer has fetched our last submission yet
     ///
     /// This method is only intended for diagnostics purposes. Please do not let
-    /// it inform your decision of reading a value or not, as that would
+    /// it inform your decision of sending or not sending a value, as that would
     /// effectively be building a very poor spinlock-based double buffer
     /// implementation. If what you truly need is a double buffer, build
     /// yourself a proper blocking one instead of wasting CPU time.
     ///
-    pub fn updated(&self) -> bool {
+    pub fn consumed(&self) -> bool {
         let back_info = self.shared.back_info.load(Ordering::Relaxed);
-        back_info & BACK_DIRTY_BIT != 0
+        !back_info.is_dirty()
     }
 
-    /// Access the output buffer directly, in non-mutable way
+    /// Check if the consumer end of the triple buffer still exists
     ///
-    /// This is simply a non-mutable version of `output_buffer()`.
-    /// For details, see the `output_buffer()` method.
+    /// Once the `Output` has been dropped, nobody will ever read the values
+    /// that you publish, so you may want to stop producing them.
     ///
-    /// This method does not update the output buffer automatically. You need to call
-    /// `update()` in order to fetch buffer updates from the producer.
-    pub fn peek_output_buffer(&self) -> &T {
-        // Access the output buffer directly
-        let output_ptr = self.shared.buffers[self.output_idx as usize].get();
-        unsafe { &*output_ptr }
+    pub fn reader_alive(&self) -> bool {
+        self.shared.output_alive.load(Ordering::Acquire)
     }
 
-    /// Access the output buffer directly
+    /// Check if another input refers to the same triple buffer as this one
     ///
-    /// This advanced interface allows you to modify the contents of the output
-    /// buffer, so that you can avoid copying the output value when this is an
-    /// expensive process. One possible application, for example, is to
-    /// post-process values from the producer before use.
+    /// Since inputs cannot be cloned, this is only true of an input and itself,
+    /// but it can be useful for bookkeeping, e.g. to look up an input in a
+    /// collection of references to inputs.
+    ///
+    pub fn same_buffer(&self, other: &Input<T>) -> bool {
+        Arc::ptr_eq(&self.shared, &other.shared)
+    }
+
+    /// Check if an output is the other end of the triple buffer of this input
+    pub fn is_paired_with(&self, output: &Output<T>) -> bool {
+        Arc::ptr_eq(&self.shared, &output.shared)
+    }
+
+    /// Label of the triple buffer, if one was set with `set_label()`
+    pub fn label(&self) -> Option<&'static str> {
+        self.shared.label.get()
+    }
+
+    /// Number of times the consumer checked for updates since our last write
+    ///
+    /// Every call to `Output::update()` counts as a read, whether it fetched a
+    /// new update or not. This can be used to adapt the publication rate to
+    /// the consumer's read rate: if this is more than 1 when you are about to
+    /// write, the consumer polled the buffer more often than you wrote to it
+    /// and saw stale data, whereas 0 means that the consumer did not even look
+    /// at your last update yet.
+    ///
+    /// This information is only an approximation, as the consumer may be
+    /// reading from the buffer concurrently.
+    ///
+    #[cfg(feature = "stats")]
+    pub fn reads_since_last_write(&self) -> u64 {
+        let reads = self.shared.reads.load(Ordering::Relaxed);
+        reads.wrapping_sub(self.reads_at_last_write) as u64
+    }
+
+    /// Total size of the updates published so far, in bytes
+    ///
+    /// By default, each update is assumed to be `size_of::<T>()` bytes large.
+    /// If the payload is a collection whose size varies from one update to the
+    /// next, use `measure_bytes_with()` to specify how its size is computed.
+    ///
+    #[cfg(feature = "stats")]
+    pub fn bytes_published(&self) -> u64 {
+        self.bytes_published
+    }
+
+    /// Specify how the size of updates is measured by `bytes_published()`
+    ///
+    /// The provided function is called on the input buffer on every publish,
+    /// so it should be cheap to compute.
+    ///
+    /// ```
+    /// # use triple_buffer::TripleBuffer;
+    /// let (mut input, _output) = TripleBuffer::new(&Vec::<u32>::new()).split();
+    /// input.measure_bytes_with(|v| v.len() * std::mem::size_of::<u32>());
+    /// input.write(vec![1, 2, 3]);
+    /// assert_eq!(input.bytes_published(), 12);
+    /// ```
+    ///
+    #[cfg(feature = "stats")]
+    pub fn measure_bytes_with(&mut self, byte_len: fn(&T) -> usize) {
+        self.byte_len = byte_len;
+    }
+
+    /// Access the input buffer directly
+    ///
+    /// This advanced interface allows you to update the input buffer in place,
+    /// so that you can avoid creating values of type T repeatedy just to push
+    /// them into the triple buffer when doing so is expensive.
     ///
     /// However, by using it, you force yourself to take into account some
     /// implementation subtleties that you could normally ignore.
     ///
-    /// First, keep in mind that you can lose access to the current output
-    /// buffer any time `read()` or `update()` is called, as it may be replaced
-    /// by an updated buffer from the producer automatically.
+    /// First, the buffer does not contain the last value that you published
+    /// (which is now available to the consumer thread). In fact, what you get
+    /// may not match _any_ value that you sent in the past, but rather be a new
+    /// value that was written in there by the consumer thread. All you can
+    /// safely assume is that the buffer contains a valid value of type T, which
+    /// you may need to "clean up" before use using a type-specific process.
     ///
-    /// Second, to reduce the potential for the aforementioned usage error, this
-    /// method does not update the output buffer automatically. You need to call
-    /// `update()` in order to fetch buffer updates from the producer.
+    /// Second, we do not send updates automatically. You need to call
+    /// `publish()` in order to propagate a buffer update to the consumer.
+    /// Alternative designs based on Drop were considered, but considered too
+    /// magical for the target audience of this interface.
     ///
-    pub fn output_buffer(&mut self) -> &mut T {
+    pub fn input_buffer(&mut self) -> &mut T {
         // This is safe because the synchronization protocol ensures that we
         // have exclusive access to this buffer.
-        let output_ptr = self.shared.buffers[self.output_idx as usize].get();
-        unsafe { &mut *output_ptr }
+        let input_ptr = self.shared.buffers[self.input_idx.get()].data.get();
+        unsafe { &mut *input_ptr }
     }
 
-    /// Update the output buffer
+    /// Access the input buffer directly, in non-mutable way
     ///
-    /// Check if the producer submitted a new data version, and if one is
-    /// available, update our output buffer to use it. Return a flag that tells
-    /// you whether such an update was carried out.
+    /// This is simply a non-mutable version of `input_buffer()`, which you can
+    /// use to inspect the input buffer through a shared reference. For example,
+    /// with collection payloads, you can check the capacity of the input buffer
+    /// to tell whether an in-place update would need to allocate memory:
     ///
-    /// Bear in mind that when this happens, you will lose any change that you
-    /// performed to the output buffer via the `output_buffer()` interface.
+    /// ```
+    /// # use triple_buffer::triple_buffer;
+    /// let (input, _output) = triple_buffer(&Vec::<u8>::new());
+    /// let needs_reserve = input.peek_input_buffer().capacity() < 1024;
+    /// # assert!(needs_reserve);
+    /// ```
     ///
-    pub fn update(&mut self) -> bool {
-        // Access the shared state
-        let shared_state = &(*self.shared);
+    /// Since an `Input` can be shared between threads, this requires the
+    /// payload to be `Sync`. Otherwise, several threads could access the input
+    /// buffer at the same time through a shared `Input`, e.g. to mutate a
+    /// `Cell`. If your payload is not `Sync`, use `input_buffer()` instead.
+    ///
+    pub fn peek_input_buffer(&self) -> &T
+    where
+        T: Sync,
+    {
+        // This is safe because the synchronization protocol ensures that we
+        // have exclusive access to this buffer, and the payload is Sync, so
+        // the Input can only be shared between threads in a safe way.
+        let input_ptr = self.shared.buffers[self.input_idx.get()].data.get();
+        unsafe { &*input_ptr }
+    }
+
+    /// Publish the current input buffer, checking for overwrites
+    ///
+    /// After updating the input buffer using `input_buffer()`, you can use this
+    /// method to publish your updates to the consumer.
+    ///
+    /// This will replace the current input buffer with another one, as you
+    /// cannot continue using the old one while the consumer is accessing it.
+    ///
+    /// It will also tell you whether you overwrote a value which was not read
+    /// by the consumer thread.
+    ///
+    #[track_caller]
+    pub fn publish(&mut self) -> bool {
+        self.publish_with_ordering(Ordering::AcqRel)
+    }
+
+    /// Like `publish()`, but with a caller-chosen memory ordering
+    ///
+    /// This is an escape hatch for experts who compose the triple buffer with
+    /// other atomics in a custom lock-free protocol, and need the atomic swap
+    /// that publishes the update to be part of the single total order of
+    /// `SeqCst` operations. Most users should stick with `publish()`.
+    ///
+    /// The swap must have at least `AcqRel` ordering, for the reasons explained
+    /// in the implementation of `publish()`: with any weaker ordering, the
+    /// producer and consumer could race on the same buffer. The only accepted
+    /// orderings are thus `AcqRel`, which is what `publish()` uses, and
+    /// `SeqCst`. Beware that `SeqCst` only applies to the swap itself. It does
+    /// not make the other atomic operations of the triple buffer, such as the
+    /// loads performed by `consumed()` or `Output::updated()`, sequentially
+    /// consistent.
+    ///
+    /// ```
+    /// # use std::sync::atomic::Ordering;
+    /// # use triple_buffer::triple_buffer;
+    /// let (mut input, mut output) = triple_buffer(&0);
+    /// *input.input_buffer() = 42;
+    /// input.publish_with_ordering(Ordering::SeqCst);
+    /// assert_eq!(*output.read_with_ordering(Ordering::SeqCst), 42);
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// If `order` is neither `AcqRel` nor `SeqCst`.
+    ///
+    #[track_caller]
+    pub fn publish_with_ordering(&mut self, order: Ordering) -> bool {
+        let order = swap_ordering(order);
+
+        // Stamp the input buffer with the sequence number of this update. The
+        // sequence number 0 is reserved for the initial buffer contents.
+        self.last_sequence = self.last_sequence.wrapping_add(1).max(1);
+        #[cfg(feature = "std")]
+        {
+            self.coalesced_pending = false;
+        }
+        let sequence_ptr = self.shared.buffers[self.input_idx.get()].sequence.get();
+        unsafe { *sequence_ptr = self.last_sequence };
+
+        // Take a snapshot of the consumer's read counter
+        #[cfg(feature = "stats")]
+        {
+            self.reads_at_last_write = self.shared.reads.load(Ordering::Relaxed);
+        }
+
+        // Account for the size of this update
+        #[cfg(feature = "stats")]
+        {
+            let byte_len = (self.byte_len)(self.input_buffer()) as u64;
+            self.bytes_published = self.bytes_published.wrapping_add(byte_len);
+        }
+
+        // Record when this update was published, for latency measurements
+        #[cfg(feature = "latency-stats")]
+        {
+            let published_at_ptr = self.shared.buffers[self.input_idx.get()].published_at.get();
+            unsafe { *published_at_ptr = Some(Instant::now()) };
+        }
+
+        // Swap the input buffer and the back buffer, setting the dirty bit.
+        // The old back buffer becomes our new input buffer.
+        let former_back_info =
+            atomic::publish_swap(&*self.shared.back_info, &mut self.input_idx, order);
+
+        // Wake up the consumer if it was waiting for us to publish an update
+        #[cfg(any(feature = "async", feature = "blocking"))]
+        if former_back_info.is_consumer_waiting() {
+            self.shared.wake_consumer();
+        }
+
+        // Notify the user-provided hook, if any
+        if let Some(hook) = self.shared.on_publish.get() {
+            hook();
+        }
+
+        // Tell whether we have overwritten unread data, treating this as an
+        // error in debug builds if the user asked us to do so.
+        let overwrote = former_back_info.is_dirty();
+        debug_assert!(
+            !(overwrote && self.forbid_overwrites),
+            "Producer overwrote a value that the consumer did not read"
+        );
+        overwrote
+    }
+
+    /// Sequence number of the last update that we published
+    ///
+    /// Before anything is published, this is the sequence number of the
+    /// initial buffer contents. It matches what `Output::sequence()` returns
+    /// once the consumer has fetched our last update.
+    ///
+    pub fn sequence(&self) -> Sequence {
+        Sequence(self.last_sequence)
+    }
+
+    /// Publish the current input buffer, unless something was published since
+    /// the sequence number `expected` was observed
+    ///
+    /// This is meant for producers which may retry an update, e.g. because
+    /// they receive data from an upstream source with at-least-once delivery
+    /// semantics. Such a producer records `sequence()` before it starts
+    /// preparing an update, and publishes it with this method. If a previous
+    /// attempt at publishing the same update went through, the sequence number
+    /// has moved on and the update is not published a second time.
+    ///
+    /// On success, the sequence number of the new update is returned. On
+    /// failure, the sequence number of the last published update is returned,
+    /// and the input buffer is left untouched.
+    ///
+    /// Since there can only be one producer, the sequence number only changes
+    /// when you publish. What this method protects against is thus not a race
+    /// with other producers, but your own retry logic publishing twice.
+    /// Whether the consumer ever saw a given update can be checked on its side
+    /// by comparing `Output::sequence()` with the returned sequence number.
+    ///
+    /// ```
+    /// # use triple_buffer::triple_buffer;
+    /// let (mut input, mut output) = triple_buffer(&0);
+    /// let token = input.sequence();
+    /// *input.input_buffer() = 42;
+    /// let published = input.publish_if_unchanged(token).unwrap();
+    ///
+    /// // Retrying with the same token does not publish the update again
+    /// assert_eq!(input.publish_if_unchanged(token), Err(published));
+    /// assert_eq!(output.read_with_sequence(), (&42, published));
+    /// ```
+    ///
+    #[track_caller]
+    pub fn publish_if_unchanged(&mut self, expected: Sequence) -> Result<Sequence, Sequence> {
+        if self.sequence() != expected {
+            return Err(self.sequence());
+        }
+        self.publish();
+        Ok(self.sequence())
+    }
+
+    /// Publish the current input buffer, then wait for the consumer to fetch it
+    ///
+    /// This testing helper is meant to be paired with
+    /// `Output::wait_and_update()` on the consumer side, using the same
+    /// two-thread barrier. It guarantees that the consumer's update fetches
+    /// exactly this publish, which lets you write deterministic tests of code
+    /// that uses triple buffers across threads.
+    ///
+    #[cfg(feature = "test-util")]
+    #[track_caller]
+    pub fn publish_and_signal(&mut self, barrier: &Barrier) -> bool {
+        // Publish, then tell the consumer that our update is ready...
+        let overwrote = self.publish();
+        barrier.wait();
+
+        // ...and wait for it to be done fetching that update
+        barrier.wait();
+        overwrote
+    }
+
+    /// Treat overwriting unread data as a bug in debug builds
+    ///
+    /// Overwriting a value that the consumer did not read yet is normal
+    /// behaviour for a triple buffer. But if your application is designed
+    /// such that the consumer should always keep up with the producer, you can
+    /// use this method to make `publish()` (and thus `write()`) panic whenever
+    /// that assumption is violated, which helps catching pacing bugs early.
+    ///
+    /// This check is only performed when debug assertions are enabled. In
+    /// release builds, overwrites remain silent. When the check fails, the
+    /// panic is reported at the location where you called `publish()` or one
+    /// of the `write()` methods, rather than inside of this crate.
+    ///
+    pub fn forbid_overwrites(&mut self, forbid: bool) {
+        self.forbid_overwrites = forbid;
+    }
+}
+//
+// Interface specific to reference-counted payloads
+impl<T: Send + Sync> Input<Arc<T>> {
+    /// Write a new value into a triple buffer of `Arc`s
+    ///
+    /// Triple buffers of `Arc<T>` let the consumer hold on to specific versions
+    /// of the value for as long as it likes, via `Output::read_arc()`, while
+    /// the producer keeps publishing new versions. This method wraps the value
+    /// into an `Arc` before publishing it. If the consumer is not holding on to
+    /// the `Arc` that is in the input buffer, its allocation is reused.
+    ///
+    /// ```
+    /// # use std::sync::Arc;
+    /// # use triple_buffer::triple_buffer;
+    /// let (mut input, mut output) = triple_buffer(&Arc::new(0));
+    /// input.write_arc(1);
+    /// let first = output.read_arc();
+    /// input.write_arc(2);
+    /// input.write_arc(3);
+    /// assert_eq!((*first, **output.read()), (1, 3));
+    /// ```
+    ///
+    #[track_caller]
+    pub fn write_arc(&mut self, value: T) {
+        // Reuse the allocation of the input buffer if we can
+        let input_buffer = self.input_buffer();
+        match Arc::get_mut(input_buffer) {
+            Some(target) => *target = value,
+            None => *input_buffer = Arc::new(value),
+        }
+
+        // Publish our update to the consumer
+        self.publish();
+    }
+}
+//
+// Internal interface
+impl<T: Send> Input<T> {
+    /// Block until the consumer has fetched the last update, or is dropped
+    ///
+    /// If a deadline is specified, give up waiting once it has passed. The
+    /// result tells whether the wait ended before the deadline.
+    ///
+    #[cfg(feature = "blocking")]
+    fn wait_for_consumer(&mut self, deadline: Option<Instant>) -> bool {
+        while self
+            .poll_consumer(|| Waiter::Thread(thread::current()))
+            .is_pending()
+        {
+            match deadline {
+                None => thread::park(),
+                Some(deadline) => {
+                    let now = Instant::now();
+                    if now >= deadline {
+                        // The consumer may have caught up right before we
+                        // stopped waiting, so check one last time
+                        self.stop_waiting();
+                        return self.consumed() || !self.reader_alive();
+                    }
+                    thread::park_timeout(deadline - now);
+                }
+            }
+        }
+        true
+    }
+
+    /// Publish the input buffer on behalf of `write_bounded_loss()`, keeping
+    /// track of consecutive overwrites
+    #[cfg(feature = "blocking")]
+    #[track_caller]
+    fn publish_bounded_loss(&mut self) {
+        if self.publish() {
+            self.overwrite_streak = self.overwrite_streak.saturating_add(1);
+        } else {
+            self.overwrite_streak = 0;
+        }
+    }
+
+    /// Check if the consumer has fetched the last update, or is dropped
+    ///
+    /// If neither is the case, the consumer is asked to wake us up using the
+    /// provided waker once it fetches an update or is dropped. Once ready,
+    /// the result tells whether the last update was fetched.
+    ///
+    #[cfg(any(feature = "async", feature = "blocking"))]
+    fn poll_consumer(&self, make_waker: impl FnOnce() -> Waiter) -> Poll<bool> {
+        // Fast path: the consumer has already fetched the last update
+        if self.consumed() {
+            return Poll::Ready(true);
+        }
+
+        // Let the consumer know how to wake us up
+        *self
+            .shared
+            .producer_waker
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner) = Some(make_waker());
+
+        // Announce that we are waiting by setting the waiting bit. Since this
+        // is a read-modify-write operation on the same atomic variable that
+        // the consumer swaps when fetching updates, either we see that the
+        // update was fetched, or the consumer sees the waiting bit and wakes
+        // us up. No wakeup can be lost.
+        let former_back_info = self
+            .shared
+            .back_info
+            .fetch_or(BackBufferInfo::PRODUCER_WAITING_BIT, Ordering::AcqRel);
+        if !former_back_info.is_dirty() || !self.reader_alive() {
+            self.shared
+                .back_info
+                .fetch_and(!BackBufferInfo::PRODUCER_WAITING_BIT, Ordering::Relaxed);
+            return Poll::Ready(!former_back_info.is_dirty());
+        }
+        Poll::Pending
+    }
+
+    /// Stop waiting for the consumer, e.g. because a future was dropped
+    ///
+    /// This clears the waiting bit and the waker registered by
+    /// `poll_consumer()`, so that the consumer does not try to wake up a
+    /// producer which is not waiting anymore.
+    ///
+    #[cfg(any(feature = "async", feature = "blocking"))]
+    fn stop_waiting(&self) {
+        self.shared
+            .back_info
+            .fetch_and(!BackBufferInfo::PRODUCER_WAITING_BIT, Ordering::Relaxed);
+        let stale_waiter = self
+            .shared
+            .producer_waker
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .take();
+        drop(stale_waiter);
+    }
+}
+//
+impl<T: Send> Drop for Input<T> {
+    fn drop(&mut self) {
+        // Release ordering ensures that the consumer observes our last publish
+        // if it observes that we are gone.
+        self.shared.input_alive.store(false, Ordering::Release);
+
+        // If the consumer is waiting for us, it must be told that it will
+        // never get what it is waiting for. See Output::drop() for details.
+        #[cfg(any(feature = "async", feature = "blocking"))]
+        {
+            let former_back_info = self
+                .shared
+                .back_info
+                .fetch_and(!BackBufferInfo::CONSUMER_WAITING_BIT, Ordering::AcqRel);
+            if former_back_info.is_consumer_waiting() {
+                self.shared.wake_consumer();
+            }
+        }
+    }
+}
+
+/// Future which resolves once the consumer has fetched a tracked value
+///
+/// This is returned by `Input::write_tracked()`, see that method for details.
+///
+#[cfg(feature = "async")]
+#[derive(Debug)]
+#[must_use = "futures do nothing unless polled"]
+pub struct ConsumedFuture<'input, T: Send> {
+    /// Producer interface which published the tracked value
+    input: &'input mut Input<T>,
+}
+//
+#[cfg(feature = "async")]
+impl<T: Send> Future for ConsumedFuture<'_, T> {
+    type Output = bool;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<bool> {
+        self.input
+            .poll_consumer(|| Waiter::Task(cx.waker().clone()))
+    }
+}
+//
+#[cfg(feature = "async")]
+impl<T: Send> Drop for ConsumedFuture<'_, T> {
+    fn drop(&mut self) {
+        self.input.stop_waiting();
+    }
+}
+
+/// Future which resolves once the producer has published an update
+///
+/// This is returned by `Output::changed()`, see that method for details.
+///
+#[cfg(feature = "async")]
+#[derive(Debug)]
+#[must_use = "futures do nothing unless polled"]
+pub struct ChangedFuture<'output, T: Send> {
+    /// Consumer interface which is waiting for an update
+    output: &'output mut Output<T>,
+}
+//
+#[cfg(feature = "async")]
+impl<T: Send> Future for ChangedFuture<'_, T> {
+    type Output = bool;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<bool> {
+        self.output
+            .poll_producer(|| Waiter::Task(cx.waker().clone()))
+    }
+}
+//
+#[cfg(feature = "async")]
+impl<T: Send> Drop for ChangedFuture<'_, T> {
+    fn drop(&mut self) {
+        self.output.stop_waiting();
+    }
+}
+
+/// Consumer interface to the triple buffer
+///
+/// The consumer of data can use this struct to access the latest published
+/// update from the producer whenever he likes. Readout is nonblocking: a
+/// collision between the producer and consumer will result in cache contention,
+/// but deadlocks and scheduling-induced slowdowns cannot happen.
+///
+/// Reading does not allocate memory or take locks either, which makes this
+/// interface suitable for use in real-time threads such as audio callbacks.
+/// See the `audio_params` example for a demonstration of this use case.
+///
+/// There is one caveat: if the producer is blocked waiting for the consumer,
+/// in `Input::write_bounded_loss()` or `Input::write_tracked()`, fetching the
+/// update that ends the wait briefly locks a mutex to wake the producer up.
+/// Real-time consumers should thus be paired with producers that do not use
+/// these methods.
+///
+#[derive(Debug)]
+pub struct Output<T: Send> {
+    /// Reference-counted shared state
+    shared: Arc<SharedState<T>>,
+
+    /// Index of the output buffer (which is private to the consumer)
+    output_idx: BufferIndex,
+
+    /// Latencies of the updates fetched so far
+    #[cfg(feature = "latency-stats")]
+    latency_histogram: LatencyHistogram,
+
+    /// Time at which we last checked for updates, if we ever did
+    #[cfg(feature = "latency-stats")]
+    last_read: Option<Instant>,
+
+    /// Time elapsed between successive checks for updates
+    #[cfg(feature = "latency-stats")]
+    read_intervals: IntervalStats,
+
+    /// User-defined ordering of the payload, checked in debug builds
+    #[cfg(debug_assertions)]
+    order_check: Option<Box<OrderCheck<T>>>,
+}
+//
+// Public interface
+impl<T: Send> Output<T> {
+    /// Construct a placeholder output that is not connected to any producer
+    ///
+    /// This can be used to initialize a struct field before the actual triple
+    /// buffer is available, and replace it with a connected output later on.
+    /// A disconnected output never receives any update, and reading from it
+    /// will always yield a default-constructed value.
+    ///
+    pub fn disconnected() -> Self
+    where
+        T: Default,
+    {
+        TripleBuffer::default().split().1
+    }
+
+    /// Construct an output whose value never changes
+    ///
+    /// This lets generic consumer code treat constant data and data that is
+    /// updated by a producer uniformly, through the `Output` type. There is
+    /// no producer, so `updated()` is always `false`, `writer_alive()` is
+    /// always `false`, and `read()` always returns `value` (unless you modify
+    /// it yourself through `output_buffer()`).
+    ///
+    /// The value is treated as if it had been published by a producer, so
+    /// that methods which tell published values apart from the initial
+    /// placeholder, like `read_or_else()`, return it too.
+    ///
+    /// ```
+    /// # use triple_buffer::Output;
+    /// let mut output = Output::constant(42);
+    /// assert!(!output.updated());
+    /// assert_eq!(*output.read(), 42);
+    /// assert_eq!(*output.read_or_else(|| 0), 42);
+    /// ```
+    ///
+    pub fn constant(value: T) -> Self
+    where
+        T: Clone,
+    {
+        // Publish the value, so that it is not mistaken for a placeholder
+        let placeholder = value.clone();
+        let (_input, mut output) = TripleBuffer::new_seeded(&placeholder, value).split();
+        output.update();
+        output
+    }
+
+    /// Treat out-of-order updates as a bug in debug builds
+    ///
+    /// The triple buffer never reorders updates, but the producer may have
+    /// bugs that make it emit data which is out of order with respect to some
+    /// application-defined ordering (e.g. timestamps going backwards). To help
+    /// catching these, you can use this method to register a predicate which
+    /// tells whether `current` may follow `previous`. It is then called on
+    /// every update fetched by `update()` (and thus `read()`), against the
+    /// previous update that was fetched, and a panic occurs if it returns
+    /// `false`.
+    ///
+    /// This check is only performed when debug assertions are enabled. It is
+    /// local to the consumer, which keeps a copy of the last value it fetched.
+    /// The first update fetched after calling this method is not checked.
+    ///
+    /// ```
+    /// # use triple_buffer::TripleBuffer;
+    /// let (mut input, mut output) = TripleBuffer::new(&0).split();
+    /// output.check_order(|previous, current| current > previous);
+    /// input.write(1);
+    /// output.read();
+    /// input.write(2);
+    /// output.read();
+    /// // Writing and reading a value smaller than 2 now panics in debug builds
+    /// ```
+    ///
+    pub fn check_order(&mut self, is_ordered: impl FnMut(&T, &T) -> bool + Send + 'static)
+    where
+        T: Clone,
+    {
+        #[cfg(debug_assertions)]
+        {
+            self.order_check = Some(Box::new(OrderCheck::new(is_ordered)));
+        }
+        #[cfg(not(debug_assertions))]
+        {
+            let _ = is_ordered;
+        }
+    }
+
+    /// Access the latest value from the triple buffer
+    ///
+    /// This is a shorthand for calling `update()`, then `peek_output_buffer()`.
+    /// If you need precise control over when buffer updates are fetched, for
+    /// example to simulate a slow consumer in a test harness, you can call
+    /// these two methods separately instead.
+    ///
+    #[track_caller]
+    pub fn read(&mut self) -> &T {
+        // Fetch updates from the producer
+        self.update();
+
+        // Give access to the output buffer
+        self.output_buffer()
+    }
+
+    /// Like `read()`, but with a caller-chosen memory ordering
+    ///
+    /// See `update_with_ordering()` for more information.
+    ///
+    #[track_caller]
+    pub fn read_with_ordering(&mut self, order: Ordering) -> &T {
+        self.update_with_ordering(order);
+        self.output_buffer()
+    }
+
+    /// Access the latest value from the triple buffer, in writable mode
+    ///
+    /// This is a shorthand for calling `update()`, then `output_buffer()`. Once
+    /// an update has been fetched, the consumer has exclusive access to the
+    /// output buffer, so you can use it as scratch space, e.g. to post-process
+    /// the value in place before using it.
+    ///
+    /// Keep in mind that your changes are never sent back to the producer, and
+    /// that they will be lost on the next call to `read()`, `read_mut()` or
+    /// `update()` if that call fetches a new value from the producer.
+    ///
+    /// ```
+    /// # use triple_buffer::triple_buffer;
+    /// let (mut input, mut output) = triple_buffer(&vec![3, 1, 2]);
+    /// input.write(vec![6, 4, 5]);
+    /// output.read_mut().sort_unstable();
+    /// assert_eq!(*output.read(), [4, 5, 6]);
+    /// ```
+    ///
+    #[track_caller]
+    pub fn read_mut(&mut self) -> &mut T {
+        self.update();
+        self.output_buffer()
+    }
+
+    /// Access the latest value from the triple buffer, if it is new
+    ///
+    /// If the producer published an update since the last time you checked,
+    /// this fetches it and gives you access to it. Otherwise, `None` is
+    /// returned. This is meant for consumers which poll the triple buffer and
+    /// only have work to do when a new value comes in.
+    ///
+    /// Unlike calling `updated()`, then `read()`, this only checks for updates
+    /// once, so it saves an atomic load on every successful poll.
+    ///
+    /// ```
+    /// # use triple_buffer::triple_buffer;
+    /// let (mut input, mut output) = triple_buffer(&0);
+    /// assert_eq!(output.poll_and_read(), None);
+    /// input.write(42);
+    /// assert_eq!(output.poll_and_read(), Some(&42));
+    /// assert_eq!(output.poll_and_read(), None);
+    /// ```
+    ///
+    #[track_caller]
+    pub fn poll_and_read(&mut self) -> Option<&T> {
+        if self.update() {
+            Some(self.peek_output_buffer())
+        } else {
+            None
+        }
+    }
+
+    /// Access the latest value from the triple buffer, and its sequence number
+    ///
+    /// This is a shorthand for calling `read()`, then `sequence()`. The
+    /// sequence number can be used to tell whether you have already processed
+    /// the value that you are looking at. See `Sequence` for more information.
+    ///
+    #[track_caller]
+    pub fn read_with_sequence(&mut self) -> (&T, Sequence) {
+        self.update();
+        (self.peek_output_buffer(), self.sequence())
+    }
+
+    /// Get an owned copy of the latest value from the triple buffer
+    ///
+    /// The reference returned by `read()` borrows the `Output`, so it cannot
+    /// be kept around while the `Output` is in use, e.g. as a field of the
+    /// struct that owns the `Output`. This method clones the latest value
+    /// instead, which is the recommended way to cache it for later use.
+    ///
+    #[track_caller]
+    pub fn fetch(&mut self) -> T
+    where
+        T: Clone,
+    {
+        self.read().clone()
+    }
+
+    /// Move the latest value out of the triple buffer
+    ///
+    /// This fetches updates like `read()`, then takes ownership of the output
+    /// buffer's contents, leaving `T::default()` in its place. It is meant for
+    /// consumers which process each update exactly once, and would rather not
+    /// borrow or clone it.
+    ///
+    /// Bear in mind that until the producer publishes a new update, further
+    /// reads will see the default value that was left behind:
+    ///
+    /// ```
+    /// # use triple_buffer::triple_buffer;
+    /// let (mut input, mut output) = triple_buffer(&Vec::<u8>::new());
+    /// input.write(vec![1, 2, 3]);
+    /// assert_eq!(output.take(), [1, 2, 3]);
+    /// assert!(output.read().is_empty());
+    /// ```
+    ///
+    /// See `replace_output_buffer()` if you would rather leave a recycled
+    /// value behind, and not fetch updates.
+    ///
+    #[track_caller]
+    pub fn take(&mut self) -> T
+    where
+        T: Default,
+    {
+        core::mem::take(self.read_mut())
+    }
+
+    /// Access the latest value, or a fallback if nothing was published yet
+    ///
+    /// Until the producer publishes its first update, the consumer sees one of
+    /// the placeholder values that the triple buffer was constructed with.
+    /// This method lets you substitute your own fallback value in this case,
+    /// without having to wrap the payload type into an `Option`. The fallback
+    /// is only computed when needed.
+    ///
+    #[track_caller]
+    pub fn read_or_else(&mut self, fallback: impl FnOnce() -> T) -> Cow<'_, T>
+    where
+        T: Clone,
+    {
+        self.update();
+        if self.sequence() == Sequence(0) {
+            Cow::Owned(fallback())
+        } else {
+            Cow::Borrowed(self.peek_output_buffer())
+        }
+    }
+
+    /// Iterate over the latest value from the triple buffer
+    ///
+    /// This infinite iterator yields an owned copy of the latest value each
+    /// time it is polled, as if by calling `fetch()`. It never waits for
+    /// updates, so the same value will be yielded repeatedly if the producer
+    /// did not publish anything in the meantime.
+    ///
+    /// ```
+    /// # use triple_buffer::triple_buffer;
+    /// let (mut input, mut output) = triple_buffer(&0);
+    /// input.write(42);
+    /// for value in output.latest_iter().take(3) {
+    ///     assert_eq!(value, 42);
+    /// }
+    /// ```
+    ///
+    pub fn latest_iter(&mut self) -> impl Iterator<Item = T> + '_
+    where
+        T: Clone,
+    {
+        core::iter::repeat_with(move || self.fetch())
+    }
+
+    /// Tell whether a buffer update is incoming from the producer
+    ///
+    /// This method is only intended for diagnostics purposes. Please do not let
+    /// it inform your decision of reading a value or not, as that would
+    /// effectively be building a very poor spinlock-based double buffer
+    /// implementation. If what you truly need is a double buffer, build
+    /// yourself a proper blocking one instead of wasting CPU time.
+    ///
+    pub fn updated(&self) -> bool {
+        let back_info = self.shared.back_info.load(Ordering::Relaxed);
+        back_info.is_dirty()
+    }
+
+    /// Check if the producer end of the triple buffer still exists
+    ///
+    /// Once the `Input` has been dropped, no new value will ever be published.
+    /// Values that were published before that remain available for reading.
+    ///
+    pub fn writer_alive(&self) -> bool {
+        self.shared.input_alive.load(Ordering::Acquire)
+    }
+
+    /// Check if another output refers to the same triple buffer as this one
+    ///
+    /// See `Input::same_buffer()` for more information.
+    ///
+    pub fn same_buffer(&self, other: &Output<T>) -> bool {
+        Arc::ptr_eq(&self.shared, &other.shared)
+    }
+
+    /// Check if an input is the other end of the triple buffer of this output
+    pub fn is_paired_with(&self, input: &Input<T>) -> bool {
+        input.is_paired_with(self)
+    }
+
+    /// Label of the triple buffer, if one was set with `set_label()`
+    pub fn label(&self) -> Option<&'static str> {
+        self.shared.label.get()
+    }
+
+    /// Wait for the producer to publish an update
+    ///
+    /// This returns a future which resolves once an update is available, which
+    /// lets async consumers wait for updates without polling in a busy loop.
+    /// The future resolves to `true` if an update is available, and to `false`
+    /// if the producer went away without publishing one. It does not fetch
+    /// the update, which is left to `read()` or `update()`:
+    ///
+    /// ```
+    /// # use triple_buffer::triple_buffer;
+    /// # use std::{future::Future, pin::pin, sync::Arc, task::{Context, Poll, Wake, Waker}};
+    /// # struct NoopWaker;
+    /// # impl Wake for NoopWaker { fn wake(self: Arc<Self>) {} }
+    /// let (mut input, mut output) = triple_buffer(&0);
+    /// # let process = |_: &i32| {};
+    /// let consumer = async move {
+    ///     while output.changed().await {
+    ///         process(output.read());
+    ///     }
+    /// };
+    /// # input.write(42);
+    /// # drop(input);
+    /// # let mut consumer = pin!(consumer);
+    /// # let waker = Waker::from(Arc::new(NoopWaker));
+    /// # let mut cx = Context::from_waker(&waker);
+    /// # assert_eq!(consumer.as_mut().poll(&mut cx), Poll::Ready(()));
+    /// ```
+    ///
+    /// Waiting has some overhead, as it requires the producer to wake up the
+    /// consumer, so it is best used by consumers which spend most of their
+    /// time idle, waiting for updates.
+    ///
+    #[cfg(feature = "async")]
+    pub fn changed(&mut self) -> ChangedFuture<'_, T> {
+        ChangedFuture { output: self }
+    }
+
+    /// Wait for the producer to publish an update, then access it
+    ///
+    /// This is a blocking alternative to `read()` for consumer threads which
+    /// have nothing else to do until a new value comes in. The thread is
+    /// parked until the producer publishes an update, and woken up by the
+    /// producer, instead of spinning. If an update is already available, it
+    /// is fetched right away.
+    ///
+    /// If the producer is dropped without publishing an update, this returns
+    /// the current value of the output buffer. Use `writer_alive()` to tell
+    /// this case apart if needed.
+    ///
+    /// ```
+    /// # use std::thread;
+    /// # use triple_buffer::triple_buffer;
+    /// let (mut input, mut output) = triple_buffer(&0);
+    /// let producer = thread::spawn(move || input.write(42));
+    /// assert_eq!(*output.wait_and_read(), 42);
+    /// # producer.join().unwrap();
+    /// ```
+    ///
+    #[cfg(feature = "blocking")]
+    #[track_caller]
+    pub fn wait_and_read(&mut self) -> &T {
+        self.wait_for_producer();
+        self.read()
+    }
+
+    /// Check if the producer is blocked, waiting for us to fetch an update
+    ///
+    /// This can happen when the producer uses a blocking write method such as
+    /// `Input::write_bounded_loss()`. If you are reading from several triple
+    /// buffers, you can use this to service the blocked producers first.
+    /// Fetching an update with `update()` or `read()` will unblock them.
+    ///
+    #[cfg(any(feature = "async", feature = "blocking"))]
+    pub fn producer_waiting(&self) -> bool {
+        let back_info = self.shared.back_info.load(Ordering::Relaxed);
+        back_info.is_producer_waiting()
+    }
+
+    /// Access the output buffer directly, in non-mutable way
+    ///
+    /// This is simply a non-mutable version of `output_buffer()`.
+    /// For details, see the `output_buffer()` method.
+    ///
+    /// This method does not update the output buffer automatically. You need to call
+    /// `update()` in order to fetch buffer updates from the producer.
+    pub fn peek_output_buffer(&self) -> &T {
+        // Access the output buffer directly
+        let output_ptr = self.shared.buffers[self.output_idx.get()].data.get();
+        unsafe { &*output_ptr }
+    }
+
+    /// Sequence number of the value in the output buffer
+    ///
+    /// Every update published by the producer gets a new sequence number,
+    /// which is greater than that of every previous update. This lets you tell
+    /// apart values that you have already processed from new ones, without
+    /// relying on the value itself or on the underlying buffer, both of which
+    /// may be reused across updates.
+    ///
+    /// Like `peek_output_buffer()`, this method does not fetch updates.
+    ///
+    pub fn sequence(&self) -> Sequence {
+        // This is safe because the synchronization protocol ensures that we
+        // have exclusive access to the output buffer.
+        let sequence_ptr = self.shared.buffers[self.output_idx.get()].sequence.get();
+        Sequence(unsafe { *sequence_ptr })
+    }
+
+    /// Index of the physical buffer that is currently used as output buffer
+    ///
+    /// The triple buffer is backed by three physical buffers, which rotate
+    /// between the producer and consumer. This tells which of them, from 0 to
+    /// 2, you are currently reading from. It only changes when `update()`, or
+    /// a method that calls it like `read()`, fetches a new value.
+    ///
+    /// This is useful when you associate external resources with each of the
+    /// physical buffers, e.g. one persistently mapped GPU buffer per physical
+    /// buffer, and need to know which of these resources to use.
+    ///
+    pub fn current_buffer_index(&self) -> usize {
+        self.output_idx.get()
+    }
+
+    /// Histogram of the time it took for fetched updates to reach the consumer
+    ///
+    /// See `LatencyHistogram` for more information on what is measured.
+    ///
+    #[cfg(feature = "latency-stats")]
+    pub fn latency_histogram(&self) -> &LatencyHistogram {
+        &self.latency_histogram
+    }
+
+    /// Extract the latency histogram, starting over with an empty one
+    ///
+    /// This is useful when you want to characterize latencies over successive
+    /// time windows, rather than over the whole lifetime of the consumer.
+    ///
+    #[cfg(feature = "latency-stats")]
+    pub fn take_latency_histogram(&mut self) -> LatencyHistogram {
+        core::mem::take(&mut self.latency_histogram)
+    }
+
+    /// Statistics on the time elapsed between successive reads
+    ///
+    /// See `IntervalStats` for more information on what is measured.
+    ///
+    #[cfg(feature = "latency-stats")]
+    pub fn read_interval_stats(&self) -> IntervalStats {
+        self.read_intervals
+    }
+
+    /// Extract the read interval statistics, starting over with empty ones
+    ///
+    /// As with `take_latency_histogram()`, this lets you monitor the cadence
+    /// of the consumer over successive time windows.
+    ///
+    #[cfg(feature = "latency-stats")]
+    pub fn take_read_interval_stats(&mut self) -> IntervalStats {
+        core::mem::take(&mut self.read_intervals)
+    }
+
+    /// Wait for the producer to publish, then update the output buffer
+    ///
+    /// This testing helper is meant to be paired with
+    /// `Input::publish_and_signal()` on the producer side, using the same
+    /// two-thread barrier. See that method for more information.
+    ///
+    #[cfg(feature = "test-util")]
+    #[track_caller]
+    pub fn wait_and_update(&mut self, barrier: &Barrier) -> bool {
+        // Wait for the producer to publish, then fetch the update...
+        barrier.wait();
+        let updated = self.update();
+
+        // ...and tell the producer that we are done
+        barrier.wait();
+        updated
+    }
+
+    /// Access the output buffer without updating it, and check for updates
+    ///
+    /// This combines `peek_output_buffer()` and `updated()`: you get the value
+    /// that you currently hold, along with a flag telling whether a newer one
+    /// is available. No update is fetched, so you can then decide for yourself
+    /// whether calling `read()` or `update()` is worthwhile.
+    ///
+    pub fn inspect(&self) -> (&T, bool) {
+        (self.peek_output_buffer(), self.updated())
+    }
+
+    /// Access the output buffer directly
+    ///
+    /// This advanced interface allows you to modify the contents of the output
+    /// buffer, so that you can avoid copying the output value when this is an
+    /// expensive process. One possible application, for example, is to
+    /// post-process values from the producer before use.
+    ///
+    /// However, by using it, you force yourself to take into account some
+    /// implementation subtleties that you could normally ignore.
+    ///
+    /// First, keep in mind that you can lose access to the current output
+    /// buffer any time `read()` or `update()` is called, as it may be replaced
+    /// by an updated buffer from the producer automatically.
+    ///
+    /// Second, to reduce the potential for the aforementioned usage error, this
+    /// method does not update the output buffer automatically. You need to call
+    /// `update()` in order to fetch buffer updates from the producer.
+    ///
+    pub fn output_buffer(&mut self) -> &mut T {
+        // This is safe because the synchronization protocol ensures that we
+        // have exclusive access to this buffer.
+        let output_ptr = self.shared.buffers[self.output_idx.get()].data.get();
+        unsafe { &mut *output_ptr }
+    }
+
+    /// Exchange the contents of the output buffer with another value
+    ///
+    /// This lets you take ownership of the current output value, for example
+    /// to forward it to the next stage of a processing pipeline without making
+    /// a copy, while giving the triple buffer a value to recycle in its place.
+    /// Downstream stages can then hand their spent values back to you, so that
+    /// you can use them as replacements later on:
+    ///
+    /// ```
+    /// # use triple_buffer::triple_buffer;
+    /// let (mut input, mut output) = triple_buffer(&Vec::<u8>::new());
+    /// input.write(vec![1, 2, 3]);
+    /// output.update();
+    ///
+    /// // Take the current value, replacing it with a recycled buffer
+    /// let recycled = Vec::with_capacity(3);
+    /// let frame = output.replace_output_buffer(recycled);
+    /// assert_eq!(frame, [1, 2, 3]);
+    /// assert!(output.peek_output_buffer().is_empty());
+    /// ```
+    ///
+    /// Like `output_buffer()`, this method does not fetch updates. And since
+    /// the replacement value may eventually be handed over to the producer, it
+    /// must be a valid value of type T from the producer's point of view.
+    ///
+    pub fn replace_output_buffer(&mut self, replacement: T) -> T {
+        core::mem::replace(self.output_buffer(), replacement)
+    }
+
+    /// Update the output buffer
+    ///
+    /// Check if the producer submitted a new data version, and if one is
+    /// available, update our output buffer to use it. Return a flag that tells
+    /// you whether such an update was carried out.
+    ///
+    /// Bear in mind that when this happens, you will lose any change that you
+    /// performed to the output buffer via the `output_buffer()` interface.
+    ///
+    #[track_caller]
+    pub fn update(&mut self) -> bool {
+        self.update_with_ordering(Ordering::AcqRel)
+    }
+
+    /// Like `update()`, but with a caller-chosen memory ordering
+    ///
+    /// This is the consumer-side counterpart of `Input::publish_with_ordering()`,
+    /// and the same rules apply. The ordering is used for the atomic swap that
+    /// fetches the update, if there is one.
+    ///
+    /// # Panics
+    ///
+    /// If `order` is neither `AcqRel` nor `SeqCst`.
+    ///
+    #[track_caller]
+    pub fn update_with_ordering(&mut self, order: Ordering) -> bool {
+        let order = swap_ordering(order);
+
+        // Access the shared state
+        let shared_state = &(*self.shared);
+
+        // Let the producer know that we checked for updates
+        #[cfg(feature = "stats")]
+        shared_state.reads.fetch_add(1, Ordering::Relaxed);
+
+        // Record how long it has been since we last checked for updates
+        #[cfg(feature = "latency-stats")]
+        {
+            let now = Instant::now();
+            if let Some(last_read) = self.last_read.replace(now) {
+                self.read_intervals.record(now - last_read);
+            }
+        }
+
+        // If an update is present in the back-buffer, exchange our output
+        // buffer with the back-buffer, thusly acquiring exclusive access to
+        // the old back buffer while giving the producer a new back-buffer to
+        // write to. The old back-buffer becomes our new output buffer.
+        let former_back_info =
+            atomic::update_swap(&*shared_state.back_info, &mut self.output_idx, order);
+        let updated = former_back_info.is_some();
+        if updated {
+            // Notify the user-provided hook, if any
+            if let Some(hook) = shared_state.on_consume.get() {
+                hook();
+            }
+
+            // Check that the payload is ordered, if the user asked us to
+            #[cfg(debug_assertions)]
+            if let Some(order_check) = &mut self.order_check {
+                // This is safe because the output buffer is ours
+                let output_ptr = shared_state.buffers[self.output_idx.get()].data.get();
+                order_check.check(unsafe { &*output_ptr });
+            }
+
+            // Wake up the producer if it was waiting for us to fetch an update
+            #[cfg(any(feature = "async", feature = "blocking"))]
+            if former_back_info.is_some_and(BackBufferInfo::is_producer_waiting) {
+                shared_state.wake_producer();
+            }
+
+            // Record how long it took for the update to reach us
+            #[cfg(feature = "latency-stats")]
+            {
+                let published_at_ptr = shared_state.buffers[self.output_idx.get()]
+                    .published_at
+                    .get();
+                if let Some(published_at) = unsafe { *published_at_ptr } {
+                    self.latency_histogram.record(published_at.elapsed());
+                }
+            }
+        }
+
+        // Tell whether an update was carried out
+        updated
+    }
+}
+//
+// Interface specific to reference-counted payloads
+impl<T: Send + Sync> Output<Arc<T>> {
+    /// Get a long-lived reference to the latest value from the triple buffer
+    ///
+    /// Unlike the reference returned by `read()`, the returned `Arc` can be
+    /// kept around while the `Output` is used to fetch newer values. See
+    /// `Input::write_arc()` for more information.
+    ///
+    #[track_caller]
+    pub fn read_arc(&mut self) -> Arc<T> {
+        self.read().clone()
+    }
+}
+//
+// Internal interface
+impl<T: Send> Output<T> {
+    /// Block until the producer has published an update, or is dropped
+    #[cfg(feature = "blocking")]
+    fn wait_for_producer(&mut self) {
+        while self
+            .poll_producer(|| Waiter::Thread(thread::current()))
+            .is_pending()
+        {
+            thread::park();
+        }
+    }
+
+    /// Check if the producer has published an update, or is dropped
+    ///
+    /// This is the consumer-side counterpart of `Input::poll_consumer()`. Once
+    /// ready, the result tells whether an update is available.
+    ///
+    #[cfg(any(feature = "async", feature = "blocking"))]
+    fn poll_producer(&self, make_waker: impl FnOnce() -> Waiter) -> Poll<bool> {
+        // Fast path: an update is already available
+        if self.updated() {
+            return Poll::Ready(true);
+        }
+
+        // Let the producer know how to wake us up
+        *self
+            .shared
+            .consumer_waker
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner) = Some(make_waker());
+
+        // Announce that we are waiting by setting the waiting bit. As in
+        // poll_consumer(), this read-modify-write operation synchronizes with
+        // the producer's swap, so either we see the update, or the producer
+        // sees the waiting bit and wakes us up.
+        let former_back_info = self
+            .shared
+            .back_info
+            .fetch_or(BackBufferInfo::CONSUMER_WAITING_BIT, Ordering::AcqRel);
+        if former_back_info.is_dirty() || !self.writer_alive() {
+            self.shared
+                .back_info
+                .fetch_and(!BackBufferInfo::CONSUMER_WAITING_BIT, Ordering::Relaxed);
+            return Poll::Ready(former_back_info.is_dirty());
+        }
+        Poll::Pending
+    }
+
+    /// Stop waiting for the producer, e.g. because a future was dropped
+    ///
+    /// This is the consumer-side counterpart of `Input::stop_waiting()`.
+    ///
+    #[cfg(feature = "async")]
+    fn stop_waiting(&self) {
+        self.shared
+            .back_info
+            .fetch_and(!BackBufferInfo::CONSUMER_WAITING_BIT, Ordering::Relaxed);
+        let stale_waiter = self
+            .shared
+            .consumer_waker
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .take();
+        drop(stale_waiter);
+    }
+}
+//
+impl<T: Send> Drop for Output<T> {
+    fn drop(&mut self) {
+        self.shared.output_alive.store(false, Ordering::Release);
+
+        // If the producer is waiting for us, it must be told that it will
+        // never get what it is waiting for. Clearing the waiting bit with an
+        // atomic read-modify-write operation ensures that either the producer
+        // sees that we are gone before going to sleep, or we see it sleeping.
+        #[cfg(any(feature = "async", feature = "blocking"))]
+        {
+            let former_back_info = self
+                .shared
+                .back_info
+                .fetch_and(!BackBufferInfo::PRODUCER_WAITING_BIT, Ordering::AcqRel);
+            if former_back_info.is_producer_waiting() {
+                self.shared.wake_producer();
+            }
+        }
+    }
+}
+
+/// Identifier of an update published into a triple buffer
+///
+/// Sequence numbers are opaque tokens which can be compared to tell which of
+/// two updates was published last. The initial value of a triple buffer has a
+/// sequence number which is smaller than that of any subsequent update.
+///
+/// If you cache some state derived from the values that you read, sequence
+/// numbers are the right thing to key that cache on. Two reads that return the
+/// same sequence number are guaranteed to return the result of the same
+/// publish. This is not true of the buffer contents, as the producer may
+/// publish the same value twice, nor of the underlying buffer, which is reused
+/// every three updates or so.
+///
+/// Sequence numbers are ordered like the underlying 64-bit counter, which will
+/// not wrap around in practice. If you need comparisons to keep working even
+/// then, use `is_newer_than()`.
+///
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct Sequence(u64);
+//
+impl Sequence {
+    /// Truth that `self` was published after `other`, even across wraparound
+    ///
+    /// Unlike the `Ord` implementation, this compares sequence numbers based
+    /// on the sign of their wrapping difference, so the result stays correct
+    /// after the underlying counter wraps around. This assumes that the two
+    /// sequence numbers are less than 2^63 publishes apart. Beyond that, the
+    /// result is meaningless, which is why this is not a valid `Ord`.
+    ///
+    pub fn is_newer_than(&self, other: &Sequence) -> bool {
+        (self.0.wrapping_sub(other.0) as i64) > 0
+    }
+
+    /// Number of updates published after `earlier`, up to and including `self`
+    ///
+    /// Every publish gets a new sequence number, including those which the
+    /// consumer never saw because a newer update overwrote them. So when the
+    /// consumer fetches two successive updates, this tells how many writes
+    /// were collapsed into the second one, e.g. for dropped frame metrics:
+    ///
+    /// ```
+    /// # use triple_buffer::triple_buffer;
+    /// let (mut input, mut output) = triple_buffer(&0);
+    /// let (_, first) = output.read_with_sequence();
+    /// input.write(1);
+    /// input.write(2);
+    /// input.write(3);
+    /// let (_, second) = output.read_with_sequence();
+    /// let dropped = second.updates_since(first) - 1;
+    /// assert_eq!(dropped, 2);
+    /// ```
+    ///
+    /// Like comparisons, this assumes that `earlier` was published less than
+    /// 2^63 updates before `self`.
+    ///
+    pub fn updates_since(self, earlier: Sequence) -> u64 {
+        let difference = self.0.wrapping_sub(earlier.0);
+        // The sequence number 0 is skipped when the producer's counter wraps
+        // around, because it is reserved for the initial buffer contents.
+        if self.0 < earlier.0 {
+            difference - 1
+        } else {
+            difference
+        }
+    }
+}
+//
+
+/// Consumer interface which hands out reference-counted snapshots
+///
+/// This wrapper around `Output` lets you get owned snapshots of the latest
+/// value from the producer, which can then be forwarded to other parts of your
+/// program, without making the payload type of the triple buffer an `Arc<T>`.
+///
+/// Each new value from the producer is cloned into an `Arc` once, when it is
+/// first read. Until the next update is fetched, further reads return clones
+/// of that same `Arc`, so the cost of cloning the value is shared by everyone
+/// who uses the snapshot.
+///
+/// This is not a method of `Output` because caching an `Arc<T>` inside of
+/// `Output` would prevent it from being sent to another thread when `T` is
+/// not `Sync`.
+///
+#[derive(Debug)]
+pub struct SnapshotOutput<T: Clone + Send> {
+    /// Output interface of the underlying triple buffer
+    output: Output<T>,
+
+    /// Snapshot of the current output buffer
+    snapshot: Arc<T>,
+}
+//
+impl<T: Clone + Send> SnapshotOutput<T> {
+    /// Wrap the output interface of a triple buffer
+    ///
+    /// The current output buffer is cloned into the initial snapshot.
+    pub fn new(output: Output<T>) -> Self {
+        let snapshot = Arc::new(output.peek_output_buffer().clone());
+        Self { output, snapshot }
+    }
+
+    /// Get a snapshot of the latest value from the triple buffer
+    #[track_caller]
+    pub fn read(&mut self) -> Arc<T> {
+        if self.output.update() {
+            self.snapshot = Arc::new(self.output.peek_output_buffer().clone());
+        }
+        self.snapshot.clone()
+    }
+
+    /// Get back the underlying output interface
+    pub fn into_inner(self) -> Output<T> {
+        self.output
+    }
+}
+
+/// Triple buffer shared state
+///
+/// In a triple buffering communication protocol, the producer and consumer
+/// share the following storage:
+///
+/// - Three memory buffers suitable for storing the data at hand
+/// - Information about the back-buffer: which buffer is the current back-buffer
+///   and whether an update was published since the last readout.
+///
+#[derive(Debug)]
+struct SharedState<T: Send> {
+    /// Data storage buffers
+    ///
+    /// This is only wrapped in `ManuallyDrop` so that the buffers can be
+    /// moved out when the shared state is dropped, or earlier by
+    /// `TripleBuffer::into_inner()`. See the `Drop` impl.
+    ///
+    buffers: ManuallyDrop<[CachePadded<Buffer<T>>; 3]>,
+
+    /// Truth that the buffers were moved out by `TripleBuffer::into_inner()`
+    buffers_taken: bool,
+
+    /// Information about the current back-buffer state
+    back_info: CachePadded<AtomicBackBufferInfo>,
+
+    /// Callback which receives the buffer contents on drop, if any
+    on_drop: UnsafeCell<Option<DropCallback<T>>>,
+
+    /// Label used to tell this triple buffer apart from others
+    label: Setting<Option<&'static str>>,
+
+    /// Hook called by the producer after publishing an update, if any
+    on_publish: Setting<Option<fn()>>,
+
+    /// Hook called by the consumer after fetching an update, if any
+    on_consume: Setting<Option<fn()>>,
+
+    /// Number of times the consumer checked for updates
+    #[cfg(feature = "stats")]
+    reads: CachePadded<AtomicUsize>,
+
+    /// Truth that the producer interface has not been dropped yet
+    input_alive: AtomicBool,
+
+    /// Truth that the consumer interface has not been dropped yet
+    output_alive: AtomicBool,
+
+    /// Way to wake up the producer, if it has ever waited for the consumer
+    #[cfg(any(feature = "async", feature = "blocking"))]
+    producer_waker: Mutex<Option<Waiter>>,
+
+    /// Way to wake up the consumer, if it has ever waited for the producer
+    #[cfg(any(feature = "async", feature = "blocking"))]
+    consumer_waker: Mutex<Option<Waiter>>,
+}
+//
+#[doc(hidden)]
+impl<T: Send> SharedState<T> {
+    /// Given (a way to generate) buffer contents and the back info, build the shared state
+    fn new(mut gen_buf_data: impl FnMut(usize) -> T, back_info: BackBufferInfo) -> Self {
+        let mut make_buf = |i| -> CachePadded<Buffer<T>> {
+            CachePadded::new(Buffer {
+                data: UnsafeCell::new(gen_buf_data(i)),
+                sequence: UnsafeCell::new(0),
+                #[cfg(feature = "latency-stats")]
+                published_at: UnsafeCell::new(None),
+            })
+        };
+        Self {
+            buffers: ManuallyDrop::new([make_buf(0), make_buf(1), make_buf(2)]),
+            buffers_taken: false,
+            back_info: CachePadded::new(AtomicBackBufferInfo::new(back_info)),
+            on_drop: UnsafeCell::new(None),
+            label: Setting(UnsafeCell::new(None)),
+            on_publish: Setting(UnsafeCell::new(None)),
+            on_consume: Setting(UnsafeCell::new(None)),
+            #[cfg(feature = "stats")]
+            reads: CachePadded::new(AtomicUsize::new(0)),
+            input_alive: AtomicBool::new(true),
+            output_alive: AtomicBool::new(true),
+            #[cfg(any(feature = "async", feature = "blocking"))]
+            producer_waker: Mutex::new(None),
+            #[cfg(any(feature = "async", feature = "blocking"))]
+            consumer_waker: Mutex::new(None),
+        }
+    }
+
+    /// Wake up the producer, which is waiting for the consumer
+    #[cfg(any(feature = "async", feature = "blocking"))]
+    fn wake_producer(&self) {
+        Waiter::wake(&self.producer_waker);
+    }
+
+    /// Wake up the consumer, which is waiting for the producer
+    #[cfg(any(feature = "async", feature = "blocking"))]
+    fn wake_consumer(&self) {
+        Waiter::wake(&self.consumer_waker);
+    }
+}
+//
+#[doc(hidden)]
+impl<T: Clone + Send> SharedState<T> {
+    /// Cloning the shared state is unsafe because you must ensure that no one
+    /// is concurrently accessing it, since &self is enough for writing.
+    unsafe fn clone(&self) -> Self {
+        let result = Self::new(
+            |i| (*self.buffers[i].data.get()).clone(),
+            self.back_info.load(Ordering::Relaxed),
+        );
+        result.label.set(self.label.get());
+        result.on_publish.set(self.on_publish.get());
+        result.on_consume.set(self.on_consume.get());
+        for (dst, src) in result.buffers.iter().zip(self.buffers.iter()) {
+            *dst.sequence.get() = *src.sequence.get();
+            #[cfg(feature = "latency-stats")]
+            {
+                *dst.published_at.get() = *src.published_at.get();
+            }
+        }
+        #[cfg(feature = "stats")]
+        result
+            .reads
+            .store(self.reads.load(Ordering::Relaxed), Ordering::Relaxed);
+        result
+    }
+}
+//
+#[doc(hidden)]
+impl<T: PartialEq + Send> SharedState<T> {
+    /// Equality is unsafe for the same reason as cloning: you must ensure that
+    /// no one is concurrently accessing the triple buffer to avoid data races.
+    unsafe fn eq(&self, other: &Self) -> bool {
+        // Check whether the contents of all buffers are equal...
+        let buffers_equal = self
+            .buffers
+            .iter()
+            .zip(other.buffers.iter())
+            .all(|tuple| -> bool {
+                let (buf1, buf2) = tuple;
+                (*buf1.data.get() == *buf2.data.get())
+                    && (*buf1.sequence.get() == *buf2.sequence.get())
+            });
+
+        // ...then check whether the rest of the shared state is equal
+        buffers_equal
+            && (self.back_info.load(Ordering::Relaxed) == other.back_info.load(Ordering::Relaxed))
+    }
+}
+//
+impl<T: Send> Drop for SharedState<T> {
+    fn drop(&mut self) {
+        if self.buffers_taken {
+            return;
+        }
+
+        // This is safe because the buffers are not used after this point
+        let buffers = unsafe { ManuallyDrop::take(&mut self.buffers) };
+        if let Some(DropCallback(mut callback)) = self.on_drop.get_mut().take() {
+            for buffer in buffers {
+                callback(buffer.into_inner().data.into_inner());
+            }
+        }
+    }
+}
+//
+unsafe impl<T: Send> Sync for SharedState<T> {}
+
+/// Way to wake up a producer or consumer which is waiting for the other side
+#[cfg(any(feature = "async", feature = "blocking"))]
+#[derive(Debug)]
+enum Waiter {
+    /// Thread blocked in a synchronous method
+    #[cfg(feature = "blocking")]
+    Thread(Thread),
+
+    /// Task awaiting a `ConsumedFuture` or `ChangedFuture`
+    #[cfg(feature = "async")]
+    Task(Waker),
+}
+//
+#[cfg(any(feature = "async", feature = "blocking"))]
+impl Waiter {
+    /// Wake up the waiter registered in some slot of the shared state, if any
+    ///
+    /// The waiter is taken out of the slot, since it registers itself again
+    /// every time it checks if it should keep waiting. Waking up happens after
+    /// the slot's lock is released, so that wakers which poll the waiting task
+    /// right away do not deadlock trying to register themselves again.
+    ///
+    fn wake(slot: &Mutex<Option<Self>>) {
+        let waiter = slot.lock().unwrap_or_else(PoisonError::into_inner).take();
+        match waiter {
+            #[cfg(feature = "blocking")]
+            Some(Self::Thread(thread)) => thread.unpark(),
+            #[cfg(feature = "async")]
+            Some(Self::Task(waker)) => waker.wake(),
+            None => {}
+        }
+    }
+}
+
+/// Callback which receives the contents of a triple buffer when it is dropped
+struct DropCallback<T>(Box<dyn FnMut(T) + Send>);
+//
+impl<T> core::fmt::Debug for DropCallback<T> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.write_str("DropCallback")
+    }
+}
+
+/// Predicate used by `OrderCheck`
+#[cfg(debug_assertions)]
+type OrderPredicate<T> = dyn FnMut(&T, &T) -> bool + Send;
+
+/// Application-defined ordering check, see `Output::check_order()`
+#[cfg(debug_assertions)]
+struct OrderCheck<T> {
+    /// Copy of the last update that was fetched by the consumer, if any
+    previous: Option<T>,
+
+    /// Predicate which tells whether an update may follow the previous one
+    is_ordered: Box<OrderPredicate<T>>,
+
+    /// Update `previous`, which requires `T: Clone`
+    remember: fn(&mut Option<T>, &T),
+}
+//
+#[cfg(debug_assertions)]
+impl<T: Clone> OrderCheck<T> {
+    /// Set up an ordering check
+    fn new(is_ordered: impl FnMut(&T, &T) -> bool + Send + 'static) -> Self {
+        Self {
+            previous: None,
+            is_ordered: Box::new(is_ordered),
+            remember: |previous, current| match previous {
+                Some(previous) => previous.clone_from(current),
+                None => *previous = Some(current.clone()),
+            },
+        }
+    }
+}
+//
+#[cfg(debug_assertions)]
+impl<T> OrderCheck<T> {
+    /// Check a freshly fetched update against the previous one
+    #[track_caller]
+    fn check(&mut self, current: &T) {
+        if let Some(previous) = &self.previous {
+            assert!(
+                (self.is_ordered)(previous, current),
+                "Consumer fetched an update which is out of order"
+            );
+        }
+        (self.remember)(&mut self.previous, current);
+    }
+}
+//
+// The contents of an OrderCheck are only accessed by exclusive reference, so
+// sharing a reference to it across threads is harmless, and should not make
+// Output<T> lose its Sync-ness.
+#[cfg(debug_assertions)]
+unsafe impl<T: Send> Sync for OrderCheck<T> {}
+//
+#[cfg(debug_assertions)]
+impl<T> core::fmt::Debug for OrderCheck<T> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.write_str("OrderCheck")
+    }
+}
+
+/// Setting of a triple buffer, which can only be changed through `TripleBuffer`
+struct Setting<S: Copy>(UnsafeCell<S>);
+//
+impl<S: Copy> Setting<S> {
+    /// Query the setting
+    fn get(&self) -> S {
+        // This is safe because settings are only modified when the triple
+        // buffer is accessed by exclusive reference, see set().
+        unsafe { *self.0.get() }
+    }
+
+    /// Modify the setting
+    ///
+    /// This is unsafe because you must ensure that no one is concurrently
+    /// accessing the shared state.
+    ///
+    unsafe fn set(&self, value: S) {
+        *self.0.get() = value;
+    }
+}
+//
+impl<S: Copy + core::fmt::Debug> core::fmt::Debug for Setting<S> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        self.get().fmt(f)
+    }
+}
+
+/// Storage buffer of a triple buffer
+///
+/// Along with the data, each buffer records the sequence number of the publish
+/// that filled it. Since buffers change hands through the back-buffer swap,
+/// this sequence number is synchronized exactly like the data itself.
+///
+#[derive(Debug)]
+struct Buffer<T> {
+    /// Data stored in this buffer
+    data: UnsafeCell<T>,
+
+    /// Sequence number of the publish that filled this buffer
+    sequence: UnsafeCell<u64>,
+
+    /// Time at which this buffer was published, if it ever was
+    #[cfg(feature = "latency-stats")]
+    published_at: UnsafeCell<Option<Instant>>,
+}
+
+/// Index of one of the three buffers of a triple buffer
+///
+/// The only way to build one outside of unit tests is `BufferIndex::new()`,
+/// which checks that the index is in range, so indexing into the buffer array
+/// with it never fails.
+///
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[repr(transparent)]
+struct BufferIndex(u8);
+//
+impl BufferIndex {
+    /// Build a buffer index, checking that it is in range
+    const fn new(idx: u8) -> Option<Self> {
+        if idx <= 2 {
+            Some(Self(idx))
+        } else {
+            None
+        }
+    }
+
+    /// Build a buffer index known to be in range at compile time
+    const fn from_const(idx: u8) -> Self {
+        match Self::new(idx) {
+            Some(idx) => idx,
+            None => panic!("Buffer index out of range"),
+        }
+    }
+
+    /// Position of the buffer in the buffer array
+    fn get(self) -> usize {
+        usize::from(self.0)
+    }
+}
+
+/// Information about the back-buffer, shared by the producer and consumer
+///
+/// This is a bitfield. Its two lowest bits hold the index of the back-buffer,
+/// its third bit is set by the producer to indicate that it published an update
+/// into the back-buffer and reset by the consumer when it fetches the update.
+/// With the `async` or `blocking` feature, its fourth bit is set by a producer
+/// which is waiting for the consumer to fetch an update, and its fifth bit is
+/// set by a consumer which is waiting for the producer to publish one.
+///
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[repr(transparent)]
+struct BackBufferInfo(u8);
+//
+impl BackBufferInfo {
+    /// Mask used to extract the back-buffer index
+    const INDEX_MASK: u8 = 0b11;
+
+    /// Bit set by the producer to signal updates
+    const DIRTY_BIT: u8 = 0b100;
+
+    /// Bit set by a blocked producer
+    #[cfg(any(feature = "async", feature = "blocking"))]
+    const PRODUCER_WAITING_BIT: u8 = 0b1000;
+
+    /// Bit set by a blocked consumer
+    #[cfg(any(feature = "async", feature = "blocking"))]
+    const CONSUMER_WAITING_BIT: u8 = 0b1_0000;
+
+    /// Bits which may be set in valid back-buffer information
+    #[cfg(not(any(feature = "async", feature = "blocking")))]
+    const VALID_BITS: u8 = Self::INDEX_MASK | Self::DIRTY_BIT;
+    #[cfg(any(feature = "async", feature = "blocking"))]
+    const VALID_BITS: u8 = Self::INDEX_MASK
+        | Self::DIRTY_BIT
+        | Self::PRODUCER_WAITING_BIT
+        | Self::CONSUMER_WAITING_BIT;
+
+    /// Back-buffer information for a back-buffer with no pending update
+    const fn clean(index: BufferIndex) -> Self {
+        Self(index.0)
+    }
+
+    /// Same back-buffer, marked as holding an update from the producer
+    const fn with_dirty(self) -> Self {
+        Self(self.0 | Self::DIRTY_BIT)
+    }
+
+    /// Index of the back-buffer
+    ///
+    /// Back-buffer information is only ever built from valid buffer indices,
+    /// so this does not need to be checked again. `validate()` makes sure.
+    ///
+    const fn index(self) -> BufferIndex {
+        BufferIndex(self.0 & Self::INDEX_MASK)
+    }
+
+    /// Truth that the back-buffer holds an update from the producer
+    const fn is_dirty(self) -> bool {
+        self.0 & Self::DIRTY_BIT != 0
+    }
+
+    /// Truth that the producer is blocked waiting for an update to be fetched
+    #[cfg(any(feature = "async", feature = "blocking"))]
+    const fn is_producer_waiting(self) -> bool {
+        self.0 & Self::PRODUCER_WAITING_BIT != 0
+    }
+
+    /// Truth that the consumer is blocked waiting for an update to be published
+    #[cfg(any(feature = "async", feature = "blocking"))]
+    const fn is_consumer_waiting(self) -> bool {
+        self.0 & Self::CONSUMER_WAITING_BIT != 0
+    }
+}
+//
+type AtomicBackBufferInfo = CoreAtomic;
+
+/// Unit tests
+#[cfg(test)]
+mod tests {
+    use super::{
+        BackBufferInfo, BackInfoAtomic, BufferIndex, Input, Output, Sequence, SharedState,
+        SnapshotOutput, TripleBuffer,
+    };
+    use std::{
+        cell::Cell,
+        fmt::Debug,
+        mem,
+        ops::Deref,
+        panic::{self, AssertUnwindSafe},
+        sync::{atomic::Ordering, Arc},
+        thread,
+        time::Duration,
+    };
+    use testbench::race_cell::{RaceCell, Racey};
+
+    /// Check that triple buffers are properly initialized
+    #[test]
+    fn initial_state() {
+        // Let's create a triple buffer
+        let mut buf = TripleBuffer::new(&42);
+        check_buf_state(&mut buf, false);
+        assert_eq!(*buf.output.read(), 42);
+    }
+
+    /// Check that disconnected placeholder halves behave as expected
+    #[test]
+    fn disconnected() {
+        // A disconnected output only ever sees the default value
+        let mut output = Output::<u32>::disconnected();
+        assert!(!output.updated());
+        assert!(!output.update());
+        assert_eq!(*output.read(), 0);
+
+        // Writes to a disconnected input go nowhere, but do not fail
+        let mut input = Input::<u32>::disconnected();
+        assert!(input.consumed());
+        input.write(42);
+        assert!(!input.consumed());
+        input.write(43);
+    }
+
+    /// Check that the physical output buffer can be identified
+    #[test]
+    fn current_buffer_index() {
+        let mut buf = TripleBuffer::new(&0);
+        let mut seen = [false; 3];
+        for value in 1..=6 {
+            let index = buf.output.current_buffer_index();
+            buf.output.update();
+            assert_eq!(buf.output.current_buffer_index(), index);
+            buf.input.write(value);
+            buf.output.update();
+            let index = buf.output.current_buffer_index();
+            assert_eq!(
+                buf.output.peek_output_buffer() as *const _,
+                buf.output.shared.buffers[index].data.get() as *const _
+            );
+            seen[index] = true;
+        }
+        assert_eq!(seen, [true; 3]);
+    }
+
+    /// Check that constant outputs behave as expected
+    #[test]
+    fn constant() {
+        let mut output = Output::constant(String::from("hello"));
+        assert!(!output.writer_alive());
+        let sequence = output.sequence();
+        assert_ne!(sequence, Sequence(0));
+        for _ in 0..3 {
+            assert!(!output.updated());
+            assert!(!output.update());
+            assert_eq!(*output.read(), "hello");
+            assert_eq!(*output.read_or_else(|| String::from("fallback")), "hello");
+            assert_eq!(output.sequence(), sequence);
+        }
+    }
+
+    /// Check that reinitializing a triple buffer resets it to a fresh state
+    #[test]
+    fn reinitialize() {
+        let mut buf = TripleBuffer::new(&vec![0u8; 16]);
+        buf.input.write(vec![1; 32]);
+        buf.output.update();
+        buf.input.write(vec![2; 64]);
+        let old_shared = as_ptr(&buf.input.shared);
+
+        buf.reinitialize(&vec![3; 8]);
+        assert_eq!(buf, TripleBuffer::new(&vec![3; 8]));
+        assert_eq!(as_ptr(&buf.input.shared), old_shared);
+        check_buf_state(&mut buf, false);
+    }
+
+    /// Check that triple buffers can be built from boxed slices
+    #[test]
+    fn from_slice_exact() {
+        // Slices with exactly three elements are accepted
+        let mut buf = TripleBuffer::from_slice_exact(vec![1, 2, 3].into_boxed_slice()).unwrap();
+        check_buf_state(&mut buf, false);
+        assert_eq!(*buf.output.read(), 1);
+        assert_eq!(*buf.input.input_buffer(), 2);
+
+        // Other slices are handed back
+        for bad_len in [0, 2, 4] {
+            let values = vec![0; bad_len].into_boxed_slice();
+            let result = TripleBuffer::from_slice_exact(values);
+            assert_eq!(result.unwrap_err().len(), bad_len);
+        }
+    }
+
+    /// Check that seeded triple buffers start with a pending update
+    #[test]
+    fn new_seeded() {
+        let mut buf = TripleBuffer::new_seeded(&0, 42);
+        check_buf_state(&mut buf, true);
+        assert_eq!(*buf.output.peek_output_buffer(), 0);
+        assert_eq!(*buf.output.read(), 42);
+        check_buf_state(&mut buf, false);
+    }
+
+    /// Check that invariant violations are detected
+    #[test]
+    fn validate() {
+        let buf = TripleBuffer::new(&0);
+        assert_eq!(buf.validate(), Ok(()));
+
+        let mut bad_idx = buf.clone();
+        bad_idx.input.input_idx = BufferIndex(3);
+        assert!(bad_idx.validate().is_err());
+
+        let mut aliased_idx = buf.clone();
+        aliased_idx.output.output_idx = aliased_idx.input.input_idx;
+        assert!(aliased_idx.validate().is_err());
+
+        let bad_bits = buf.clone();
+        bad_bits
+            .input
+            .shared
+            .back_info
+            .store(BackBufferInfo(0b100_0000), Ordering::Relaxed);
+        assert!(bad_bits.validate().is_err());
+
+        #[cfg(any(feature = "async", feature = "blocking"))]
+        {
+            let waiting = buf.clone();
+            waiting.input.shared.back_info.store(
+                BackBufferInfo(
+                    BackBufferInfo::PRODUCER_WAITING_BIT | BackBufferInfo::CONSUMER_WAITING_BIT,
+                ),
+                Ordering::Relaxed,
+            );
+            assert_eq!(waiting.validate(), Ok(()));
+        }
+    }
+
+    /// Check that the triple buffer and its halves can be sent across threads
+    #[test]
+    fn send_sync() {
+        fn assert_send<T: Send>() {}
+        fn assert_send_sync<T: Send + Sync>() {}
+
+        // Payloads that can be shared across threads can be shared through
+        // the triple buffer as well
+        assert_send_sync::<TripleBuffer<i32>>();
+        assert_send_sync::<Input<i32>>();
+        assert_send_sync::<Output<i32>>();
+
+        // Payloads that can only be sent across threads can be sent through
+        // the triple buffer as well
+        assert_send::<TripleBuffer<Cell<i32>>>();
+        assert_send::<Input<Cell<i32>>>();
+        assert_send::<Output<Cell<i32>>>();
+
+        // Wrappers around Output must preserve this property
+        assert_send::<SnapshotOutput<i32>>();
+        #[cfg(feature = "std")]
+        assert_send::<crate::OutputReader<Vec<u8>>>();
+    }
+
+    /// Check that the shared state's unsafe equality operator works
+    #[test]
+    fn partial_eq_shared() {
+        // Let's create some dummy shared state
+        let dummy_state = SharedState::<u16>::new(|i| [111, 222, 333][i], back_info(2, false));
+
+        // Check that the dummy state is equal to itself
+        assert!(unsafe { dummy_state.eq(&dummy_state) });
+
+        // Check that it's not equal to a state where buffer contents differ
+        assert!(unsafe {
+            !dummy_state.eq(&SharedState::<u16>::new(
+                |i| [114, 222, 333][i],
+                back_info(2, false),
+            ))
+        });
+        assert!(unsafe {
+            !dummy_state.eq(&SharedState::<u16>::new(
+                |i| [111, 225, 333][i],
+                back_info(2, false),
+            ))
+        });
+        assert!(unsafe {
+            !dummy_state.eq(&SharedState::<u16>::new(
+                |i| [111, 222, 336][i],
+                back_info(2, false),
+            ))
+        });
+
+        // Check that it's not equal to a state where the back info differs
+        assert!(unsafe {
+            !dummy_state.eq(&SharedState::<u16>::new(
+                |i| [111, 222, 333][i],
+                back_info(2, true),
+            ))
+        });
+        assert!(unsafe {
+            !dummy_state.eq(&SharedState::<u16>::new(
+                |i| [111, 222, 333][i],
+                back_info(1, false),
+            ))
+        });
+    }
+
+    /// Check that TripleBuffer's PartialEq impl works
+    #[test]
+    fn partial_eq() {
+        // Create a triple buffer
+        let buf = TripleBuffer::new(&"test");
+
+        // Check that it is equal to itself
+        assert_eq!(buf, buf);
+
+        // Make another buffer with different contents. As buffer creation is
+        // deterministic, this should only have an impact on the shared state,
+        // but the buffers should nevertheless be considered different.
+        let buf2 = TripleBuffer::new(&"taste");
+        assert_eq!(buf.input.input_idx, buf2.input.input_idx);
+        assert_eq!(buf.output.output_idx, buf2.output.output_idx);
+        assert!(buf != buf2);
+
+        // Check that changing either the input or output buffer index will
+        // also lead two TripleBuffers to be considered different (this test
+        // technically creates an invalid TripleBuffer state, but it's the only
+        // way to check that the PartialEq impl is exhaustive)
+        let mut buf3 = TripleBuffer::new(&"test");
+        assert_eq!(buf, buf3);
+        let old_input_idx = buf3.input.input_idx;
+        buf3.input.input_idx = buf3.output.output_idx;
+        assert!(buf != buf3);
+        buf3.input.input_idx = old_input_idx;
+        buf3.output.output_idx = old_input_idx;
+        assert!(buf != buf3);
+    }
+
+    /// Check that the shared state's unsafe clone operator works
+    #[test]
+    fn clone_shared() {
+        // Let's create some dummy shared state
+        let dummy_state = SharedState::<u8>::new(|i| [123, 231, 132][i], back_info(1, true));
+
+        // Now, try to clone it
+        let dummy_state_copy = unsafe { dummy_state.clone() };
+
+        // Check that the contents of the original state did not change
+        assert!(unsafe {
+            dummy_state.eq(&SharedState::<u8>::new(
+                |i| [123, 231, 132][i],
+                back_info(1, true),
+            ))
+        });
+
+        // Check that the contents of the original and final state are identical
+        assert!(unsafe { dummy_state.eq(&dummy_state_copy) });
+    }
+
+    /// Check that TripleBuffer's Clone impl works
+    #[test]
+    fn clone() {
+        // Create a triple buffer
+        let mut buf = TripleBuffer::new(&4.2);
+
+        // Put it in a nontrivial state
+        unsafe {
+            *buf.input.shared.buffers[0].data.get() = 1.2;
+            *buf.input.shared.buffers[1].data.get() = 3.4;
+            *buf.input.shared.buffers[2].data.get() = 5.6;
+        }
+        buf.input
+            .shared
+            .back_info
+            .store(back_info(1, true), Ordering::Relaxed);
+        buf.input.input_idx = BufferIndex(2);
+        buf.output.output_idx = BufferIndex(0);
+
+        // Now clone it
+        let buf_clone = buf.clone();
+
+        // Check that the clone uses its own, separate shared data storage
+        assert_eq!(
+            as_ptr(&buf_clone.output.shared),
+            as_ptr(&buf_clone.output.shared)
+        );
+        assert!(as_ptr(&buf_clone.input.shared) != as_ptr(&buf.input.shared));
+
+        // Check that it is identical from PartialEq's point of view
+        assert_eq!(buf, buf_clone);
+
+        // Check that the contents of the original buffer did not change
+        unsafe {
+            assert_eq!(*buf.input.shared.buffers[0].data.get(), 1.2);
+            assert_eq!(*buf.input.shared.buffers[1].data.get(), 3.4);
+            assert_eq!(*buf.input.shared.buffers[2].data.get(), 5.6);
+        }
+        assert_eq!(
+            buf.input.shared.back_info.load(Ordering::Relaxed),
+            back_info(1, true)
+        );
+        assert_eq!(buf.input.input_idx, BufferIndex(2));
+        assert_eq!(buf.output.output_idx, BufferIndex(0));
+    }
+
+    /// Check that the low-level publish/update primitives work
+    #[test]
+    fn swaps() {
+        // Create a new buffer, and a way to track any changes to it
+        let mut buf = TripleBuffer::new(&[123, 456]);
+        let old_buf = buf.clone();
+        let old_input_idx = old_buf.input.input_idx;
+        let old_shared = &old_buf.input.shared;
+        let old_back_info = old_shared.back_info.load(Ordering::Relaxed);
+        let old_back_idx = old_back_info.index();
+        let old_output_idx = old_buf.output.output_idx;
+
+        // Check that updating from a clean state works
+        assert!(!buf.output.update());
+        assert_eq!(buf, old_buf);
+        check_buf_state(&mut buf, false);
+
+        // Check that publishing from a clean state works
+        assert!(!buf.input.publish());
+        let mut expected_buf = old_buf.clone();
+        expected_buf.input.input_idx = old_back_idx;
+        expected_buf.input.last_sequence = 1;
+        let expected_shared = &expected_buf.input.shared;
+        unsafe { *expected_shared.buffers[old_input_idx.get()].sequence.get() = 1 };
+        expected_shared.back_info.store(
+            BackBufferInfo::clean(old_input_idx).with_dirty(),
+            Ordering::Relaxed,
+        );
+        assert_eq!(buf, expected_buf);
+        check_buf_state(&mut buf, true);
+
+        // Check that overwriting a dirty state works
+        assert!(buf.input.publish());
+        let mut expected_buf = old_buf.clone();
+        expected_buf.input.input_idx = old_input_idx;
+        expected_buf.input.last_sequence = 2;
+        let expected_shared = &expected_buf.input.shared;
+        unsafe {
+            *expected_shared.buffers[old_input_idx.get()].sequence.get() = 1;
+            *expected_shared.buffers[old_back_idx.get()].sequence.get() = 2;
+        }
+        expected_shared.back_info.store(
+            BackBufferInfo::clean(old_back_idx).with_dirty(),
+            Ordering::Relaxed,
+        );
+        assert_eq!(buf, expected_buf);
+        check_buf_state(&mut buf, true);
+
+        // Check that updating from a dirty state works
+        assert!(buf.output.update());
+        expected_buf.output.output_idx = old_back_idx;
+        expected_buf
+            .output
+            .shared
+            .back_info
+            .store(BackBufferInfo::clean(old_output_idx), Ordering::Relaxed);
+        assert_eq!(buf, expected_buf);
+        check_buf_state(&mut buf, false);
+    }
+
+    /// Check that overwrites can be turned into errors in debug builds
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic(expected = "Producer overwrote a value")]
+    fn forbidden_overwrite() {
+        let mut buf = TripleBuffer::new(&0);
+        buf.input.forbid_overwrites(true);
+        buf.input.write(1);
+        buf.output.read();
+        buf.input.write(2);
+        buf.input.write(3);
+    }
+
+    /// Check that out-of-order updates can be turned into errors in debug builds
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic(expected = "Consumer fetched an update which is out of order")]
+    fn check_order() {
+        let mut buf = TripleBuffer::new(&0);
+        buf.output
+            .check_order(|previous, current| current > previous);
+
+        // The initial value is not checked, nor is the first update
+        buf.input.write(0);
+        buf.output.read();
+
+        // Ordered updates, overwritten or not, are fine
+        buf.input.write(1);
+        buf.output.read();
+        buf.input.write(3);
+        buf.input.write(2);
+        buf.output.read();
+
+        // Reinitializing forgets about the previous value
+        buf.reinitialize(&0);
+        buf.input.write(0);
+        buf.output.read();
+
+        // Unordered updates are not
+        buf.input.write(0);
+        buf.output.read();
+    }
+
+    /// Check that all buffers can be modified before splitting
+    #[test]
+    fn for_each_buffer_mut() {
+        let mut buf = TripleBuffer::new(&Vec::<u8>::new());
+        buf.for_each_buffer_mut(|v| v.reserve(42));
+
+        // Whatever buffer we end up in, enough capacity should be available
+        for _ in 0..3 {
+            assert!(buf.input.input_buffer().capacity() >= 42);
+            assert!(buf.output.output_buffer().capacity() >= 42);
+            buf.input.publish();
+            buf.output.update();
+        }
+
+        // This also works after splitting, by reuniting the halves first
+        let (mut input, output) = TripleBuffer::new(&Vec::<u8>::new()).split();
+        input.write(vec![1]);
+        let mut buf = TripleBuffer::reunite(input, output).unwrap();
+        buf.for_each_buffer_mut(|v| v.reserve(42));
+        let (mut input, mut output) = buf.split();
+        assert_eq!(*output.read(), [1]);
+        for _ in 0..3 {
+            assert!(input.input_buffer().capacity() >= 42);
+            assert!(output.output_buffer().capacity() >= 42);
+            input.publish();
+            output.update();
+        }
+    }
+
+    /// Check that fetching returns an owned copy of the latest value
+    #[test]
+    fn fetch() {
+        let mut buf = TripleBuffer::new(&String::from("Hello"));
+        let cached = buf.output.fetch();
+        buf.input.write(String::from("world"));
+        assert_eq!(cached, "Hello");
+        assert_eq!(buf.output.fetch(), "world");
+        assert!(!buf.output.updated());
+    }
+
+    /// Check that sequence numbers grow with each update
+    #[test]
+    fn sequence() {
+        let mut buf = TripleBuffer::new(&0);
+        let initial = buf.output.sequence();
+
+        // Sequence numbers are only updated when an update is fetched
+        buf.input.write(1);
+        assert_eq!(buf.output.sequence(), initial);
+        buf.output.update();
+        let first = buf.output.sequence();
+        assert!(first > initial);
+
+        // Overwritten updates still consume sequence numbers
+        buf.input.write(2);
+        buf.input.write(3);
+        buf.output.update();
+        let third = buf.output.sequence();
+        assert!(third > first);
+        assert_eq!(third.updates_since(first), 2);
+        assert_eq!(third.updates_since(initial), 3);
+        assert_eq!(third.updates_since(third), 0);
+
+        // Without a new update, the sequence number stays the same
+        buf.output.update();
+        assert_eq!(buf.output.sequence(), third);
+
+        // Republishing an identical value still yields a new sequence number
+        buf.input.write(3);
+        let (value, fourth) = buf.output.read_with_sequence();
+        assert_eq!(*value, 3);
+        assert!(fourth > third);
+    }
+
+    /// Check that sequence numbers can be compared across wraparound
+    #[test]
+    fn sequence_wraparound() {
+        let max = Sequence(u64::MAX);
+        let wrapped = Sequence(0);
+        assert!(wrapped.is_newer_than(&max));
+        assert!(!max.is_newer_than(&wrapped));
+        assert!(Sequence(5).is_newer_than(&Sequence(u64::MAX - 5)));
+        assert!(!max.is_newer_than(&max));
+
+        // This works as long as sequences are < 2^63 apart
+        let half = 1u64 << 63;
+        assert!(Sequence(half - 1).is_newer_than(&Sequence(0)));
+        assert!(!Sequence(half).is_newer_than(&Sequence(0)));
+
+        // Ord is the plain counter ordering, which is transitive
+        let quarter = 1u64 << 62;
+        assert!(Sequence(0) < Sequence(quarter));
+        assert!(Sequence(quarter) < Sequence(half + 1));
+        assert!(Sequence(0) < Sequence(half + 1));
+
+        // Publishing keeps working when the producer's counter wraps around
+        let mut buf = TripleBuffer::new(&0);
+        buf.input.last_sequence = u64::MAX - 1;
+        buf.input.write(1);
+        let before = buf.output.read_with_sequence().1;
+        buf.input.write(2);
+        let (value, after) = buf.output.read_with_sequence();
+        assert_eq!(*value, 2);
+        assert_eq!(after, Sequence(1));
+        assert!(after.is_newer_than(&before));
+        assert_eq!(after.updates_since(before), 1);
+        buf.input.write(3);
+        assert_eq!(buf.output.read_with_sequence().1.updates_since(before), 2);
+    }
+
+    /// Check that inspecting the output does not fetch updates
+    #[test]
+    fn inspect() {
+        let mut buf = TripleBuffer::new(&1);
+        assert_eq!(buf.output.inspect(), (&1, false));
+
+        // A pending update is reported, but not fetched
+        buf.input.write(2);
+        let old_buf = buf.clone();
+        assert_eq!(buf.output.inspect(), (&1, true));
+        assert_eq!(buf, old_buf);
+
+        // Once the update is fetched, the new value is seen
+        buf.output.update();
+        assert_eq!(buf.output.inspect(), (&2, false));
+    }
+
+    /// Check that snapshots are only taken once per update
+    #[test]
+    fn snapshots() {
+        let (mut input, output) = TripleBuffer::new(&1).split();
+        let mut snapshots = SnapshotOutput::new(output);
+
+        // Without updates, the same snapshot is handed out
+        let first = snapshots.read();
+        assert_eq!(*first, 1);
+        assert!(Arc::ptr_eq(&first, &snapshots.read()));
+
+        // After an update, a new snapshot is taken, and the old one is intact
+        input.write(2);
+        let second = snapshots.read();
+        assert_eq!(*second, 2);
+        assert_eq!(*first, 1);
+        assert!(Arc::ptr_eq(&second, &snapshots.read()));
+    }
+
+    /// Check that values can be written by reference
+    #[test]
+    fn write_ref() {
+        let mut buf = TripleBuffer::new(&String::new());
+        let value = String::from("Hello");
+        buf.input.write_ref(&value);
+        assert_eq!(value, "Hello");
+        assert_eq!(*buf.output.read(), "Hello");
+    }
+
+    /// Check that writing by reference reuses the input buffer's allocation
+    #[test]
+    fn write_ref_reuses_allocation() {
+        let mut buf = TripleBuffer::new(&Vec::<u32>::new());
+        let value = vec![1, 2, 3];
+
+        // Go through all buffers once so that each of them gets an allocation
+        for _ in 0..3 {
+            buf.input.write_ref(&value);
+            buf.output.update();
+        }
+
+        // From this point on, writes should not reallocate
+        for _ in 0..6 {
+            let input_idx = buf.input.input_idx.get();
+            let old_ptr = buf.input.input_buffer().as_ptr();
+            buf.input.write_ref(&value);
+            let new_ptr = unsafe { (*buf.input.shared.buffers[input_idx].data.get()).as_ptr() };
+            assert_eq!(old_ptr, new_ptr);
+            assert_eq!(*buf.output.read(), value);
+        }
+    }
+
+    /// Check that the buffer reclaimed by a write can be accessed
+    #[test]
+    fn write_reclaiming() {
+        let mut buf = TripleBuffer::new(&0);
+        let old_back_idx = buf.input.shared.back_info.load(Ordering::Relaxed).index();
+        *buf.input.write_reclaiming(1) = 2;
+        assert_eq!(buf.input.input_idx, old_back_idx);
+        assert_eq!(*buf.input.input_buffer(), 2);
+        assert_eq!(*buf.output.read(), 1);
+    }
+
+    /// Check that coalesced writes are only published once per time window
+    #[test]
+    #[cfg(feature = "std")]
+    fn coalesced_write() {
+        let mut buf = TripleBuffer::new(&0);
+
+        // The first coalesced write is always published
+        assert!(buf.input.write_coalesced(1, Duration::from_secs(3600)));
+        assert_eq!(*buf.output.read(), 1);
+
+        // Further writes within the time window are kept in the input buffer
+        assert!(!buf.input.write_coalesced(2, Duration::from_secs(3600)));
+        assert!(!buf.input.write_coalesced(3, Duration::from_secs(3600)));
+        assert!(!buf.output.updated());
+        assert_eq!(*buf.input.input_buffer(), 3);
+
+        // Once the time window has elapsed, the latest value gets published
+        assert!(buf.input.write_coalesced(4, Duration::ZERO));
+        assert_eq!(*buf.output.read(), 4);
+        assert!(!buf.input.flush());
+
+        // Pending values can be flushed, exactly once
+        assert!(!buf.input.write_coalesced(5, Duration::from_secs(3600)));
+        assert!(buf.input.flush());
+        assert!(!buf.input.flush());
+        assert_eq!(*buf.output.read(), 5);
+        assert!(!buf.output.update());
+
+        // Publishing by other means also publishes the pending value
+        assert!(!buf.input.write_coalesced(6, Duration::from_secs(3600)));
+        buf.input.publish();
+        assert!(!buf.input.flush());
+        assert_eq!(*buf.output.read(), 6);
+    }
+
+    /// Check that the lockstep testing helpers sequence updates as expected
+    #[test]
+    #[cfg(feature = "test-util")]
+    fn lockstep() {
+        let (mut input, mut output) = TripleBuffer::new(&0).split();
+        let barrier = Arc::new(std::sync::Barrier::new(2));
+        let producer_barrier = barrier.clone();
+        let producer = thread::spawn(move || {
+            for value in 1..=10 {
+                *input.input_buffer() = value;
+                assert!(!input.publish_and_signal(&producer_barrier));
+            }
+        });
+        for value in 1..=10 {
+            assert!(output.wait_and_update(&barrier));
+            assert_eq!(*output.peek_output_buffer(), value);
+        }
+        producer.join().unwrap();
+    }
+
+    /// Check that each end of the triple buffer can tell if the other is alive
+    #[test]
+    fn liveness() {
+        let (input, output) = TripleBuffer::new(&0).split();
+        assert!(input.reader_alive());
+        assert!(output.writer_alive());
+        drop(input);
+        assert!(!output.writer_alive());
+
+        let (input, output) = TripleBuffer::new(&0).split();
+        drop(output);
+        assert!(!input.reader_alive());
+
+        // Disconnected interfaces have no counterpart
+        assert!(!Input::<u8>::disconnected().reader_alive());
+        assert!(!Output::<u8>::disconnected().writer_alive());
+    }
+
+    /// Check that a fallback can be provided until the producer publishes
+    #[test]
+    fn read_or_else() {
+        let mut buf = TripleBuffer::new(&0);
+        assert_eq!(*buf.output.read_or_else(|| 42), 42);
+
+        // Once something is published, the fallback is not computed anymore
+        buf.input.write(1);
+        assert_eq!(*buf.output.read_or_else(|| unreachable!()), 1);
+        buf.input.write(0);
+        assert_eq!(*buf.output.read_or_else(|| unreachable!()), 0);
+
+        // Seeded triple buffers count as having published their first value
+        let mut buf = TripleBuffer::new_seeded(&0, 1);
+        assert_eq!(*buf.output.read_or_else(|| unreachable!()), 1);
+    }
+
+    /// Check that polling only yields new values
+    #[test]
+    fn poll_and_read() {
+        let mut buf = TripleBuffer::new(&0);
+        assert_eq!(buf.output.poll_and_read(), None);
+        buf.input.write(1);
+        buf.input.write(2);
+        assert_eq!(buf.output.poll_and_read(), Some(&2));
+        assert!(buf.input.consumed());
+        assert_eq!(buf.output.poll_and_read(), None);
+        assert_eq!(*buf.output.read(), 2);
+    }
+
+    /// Check that the hot-path types do not grow by accident
+    ///
+    /// If this test fails after adding a field, make sure that the extra memory
+    /// footprint is worth it, or put the field behind a feature flag or in a
+    /// separate allocation, then update the expected sizes.
+    ///
+    #[test]
+    #[cfg(any(target_arch = "x86_64", target_arch = "aarch64"))]
+    fn sizes() {
+        let mut input_size = 24;
+        let mut output_size = 16;
+        let mut shared_size = 640;
+        if cfg!(debug_assertions) {
+            output_size += 8;
+        }
+        if cfg!(feature = "std") {
+            input_size += 16;
+        }
+        if cfg!(feature = "stats") {
+            input_size += 24;
+            shared_size += 128;
+        }
+        if cfg!(feature = "latency-stats") {
+            output_size += 600;
+        }
+        assert_eq!(mem::size_of::<Input<u32>>(), input_size);
+        assert_eq!(mem::size_of::<Output<u32>>(), output_size);
+        assert_eq!(mem::size_of::<SharedState<u32>>(), shared_size);
+    }
+
+    /// Check that values can be built in place inside of the input buffer
+    #[test]
+    fn write_with() {
+        let mut buf = TripleBuffer::new(&String::from("initial"));
+        let mut buffers = Vec::new();
+        for word in ["one", "two", "three", "four"] {
+            buf.input.write_with(|s| {
+                buffers.push(s.as_ptr());
+                s.clear();
+                s.push_str(word);
+            });
+            assert_eq!(*buf.output.read(), word);
+        }
+
+        // Allocations were reused as the buffers rotated
+        buffers.sort_unstable();
+        buffers.dedup();
+        assert_eq!(buffers.len(), 3);
+    }
+
+    /// Check that overwritten values can be reclaimed by the producer
+    #[test]
+    fn write_and_reclaim() {
+        let mut buf = TripleBuffer::new(&vec![0u8]);
+        assert_eq!(buf.input.write_and_reclaim(vec![1]), None);
+        assert_eq!(buf.input.write_and_reclaim(vec![2]), Some(vec![1]));
+        assert_eq!(buf.input.write_and_reclaim(vec![3]), Some(vec![2]));
+        assert_eq!(*buf.output.read(), [3]);
+
+        // Values which the consumer fetched are not reclaimed
+        assert_eq!(buf.input.write_and_reclaim(vec![4]), None);
+        assert_eq!(*buf.output.peek_output_buffer(), [3]);
+        assert_eq!(*buf.output.read(), [4]);
+        assert_eq!(buf.input.write_and_reclaim(vec![5]), None);
+        assert_eq!(*buf.output.read(), [5]);
+    }
+
+    /// Check that the consumer can move the value that it just read out
+    #[test]
+    fn take() {
+        let mut buf = TripleBuffer::new(&vec![0u8]);
+        buf.input.write(vec![1, 2]);
+        assert_eq!(buf.output.take(), [1, 2]);
+        assert!(buf.input.consumed());
+        assert!(buf.output.read().is_empty());
+
+        // Subsequent writes still go through
+        buf.input.write(vec![3]);
+        assert_eq!(*buf.output.read(), [3]);
+        assert_eq!(buf.output.take(), [3]);
+        assert!(buf.output.take().is_empty());
+    }
+
+    /// Check that the consumer can modify the value that it just read
+    #[test]
+    fn read_mut() {
+        let mut buf = TripleBuffer::new(&0);
+        buf.input.write(1);
+        *buf.output.read_mut() += 1;
+        assert_eq!(*buf.output.read(), 2);
+        assert!(buf.input.consumed());
+
+        // Changes are lost once a new value is fetched
+        *buf.output.read_mut() += 1;
+        buf.input.write(4);
+        assert_eq!(*buf.output.read_mut(), 4);
+    }
+
+    /// Check that swaps can use a stronger memory ordering
+    #[test]
+    fn swap_ordering() {
+        let mut buf = TripleBuffer::new(&0);
+        assert!(!buf.input.publish_with_ordering(Ordering::SeqCst));
+        assert!(buf.input.publish_with_ordering(Ordering::AcqRel));
+        assert_eq!(*buf.output.read_with_ordering(Ordering::SeqCst), 0);
+        assert!(buf.input.consumed());
+        buf.input.write(1);
+        assert!(buf.output.update_with_ordering(Ordering::AcqRel));
+        assert_eq!(*buf.output.peek_output_buffer(), 1);
+    }
+
+    /// Check that swaps cannot use a weaker memory ordering
+    #[test]
+    #[should_panic(expected = "must use AcqRel or SeqCst ordering, not Release")]
+    fn weak_swap_ordering() {
+        let (mut input, _output) = TripleBuffer::new(&0).split();
+        input.publish_with_ordering(Ordering::Release);
+    }
+
+    /// Waker which does nothing, for polling futures by hand
+    #[cfg(feature = "async")]
+    struct NoopWaker;
+    //
+    #[cfg(feature = "async")]
+    impl std::task::Wake for NoopWaker {
+        fn wake(self: Arc<Self>) {}
+    }
+
+    /// Minimal executor which parks the thread until the future is woken
+    #[cfg(feature = "async")]
+    fn block_on<F: std::future::Future>(future: F) -> F::Output {
+        use std::task::{Context, Poll, Wake, Waker};
+        struct ThreadWaker(thread::Thread);
+        impl Wake for ThreadWaker {
+            fn wake(self: Arc<Self>) {
+                self.0.unpark();
+            }
+        }
+        let waker = Waker::from(Arc::new(ThreadWaker(thread::current())));
+        let mut cx = Context::from_waker(&waker);
+        let mut future = std::pin::pin!(future);
+        loop {
+            if let Poll::Ready(output) = future.as_mut().poll(&mut cx) {
+                return output;
+            }
+            thread::park();
+        }
+    }
+
+    /// Check that async producers can wait for a value to be fetched
+    #[test]
+    #[cfg(feature = "async")]
+    fn write_tracked() {
+        use std::{
+            future::Future,
+            pin::Pin,
+            task::{Context, Poll, Waker},
+        };
+
+        // The future stays pending until the consumer fetches the value
+        let (mut input, mut output) = TripleBuffer::new(&0).split();
+        {
+            let waker = Waker::from(Arc::new(NoopWaker));
+            let mut cx = Context::from_waker(&waker);
+            let mut future = input.write_tracked(1);
+            assert!(Pin::new(&mut future).poll(&mut cx).is_pending());
+            assert!(output.producer_waiting());
+            assert_eq!(*output.read(), 1);
+            assert_eq!(Pin::new(&mut future).poll(&mut cx), Poll::Ready(true));
+        }
+
+        // The consumer wakes up the producer task when it fetches the value
+        let consumer = thread::spawn(move || {
+            while *output.read() != 2 {
+                thread::yield_now();
+            }
+            output
+        });
+        assert!(block_on(input.write_tracked(2)));
+        let output = consumer.join().unwrap();
+
+        // The future also resolves if the consumer goes away
+        drop(output);
+        assert!(!block_on(input.write_tracked(3)));
+    }
+
+    /// Check that async consumers can wait for an update to be published
+    #[test]
+    #[cfg(feature = "async")]
+    fn changed() {
+        use std::{
+            future::Future,
+            pin::Pin,
+            task::{Context, Poll, Waker},
+        };
+
+        // The future stays pending until the producer publishes an update
+        let (mut input, mut output) = TripleBuffer::new(&0).split();
+        {
+            let waker = Waker::from(Arc::new(NoopWaker));
+            let mut cx = Context::from_waker(&waker);
+            let mut future = output.changed();
+            assert!(Pin::new(&mut future).poll(&mut cx).is_pending());
+            input.write(1);
+            assert_eq!(Pin::new(&mut future).poll(&mut cx), Poll::Ready(true));
+        }
+
+        // It resolves right away if an update is already available...
+        assert!(block_on(output.changed()));
+        assert_eq!(*output.read(), 1);
+
+        // ...and otherwise, the producer wakes up the consumer task
+        let producer = thread::spawn(move || {
+            thread::sleep(Duration::from_millis(10));
+            input.write(2);
+            input
+        });
+        assert!(block_on(output.changed()));
+        assert_eq!(*output.read(), 2);
+        let input = producer.join().unwrap();
+
+        // The future also resolves if the producer goes away
+        let producer = thread::spawn(move || {
+            thread::sleep(Duration::from_millis(10));
+            drop(input);
+        });
+        assert!(!block_on(output.changed()));
+        producer.join().unwrap();
+    }
+
+    /// Check that dropping a pending future stops the wait
+    #[test]
+    #[cfg(feature = "async")]
+    fn cancel_wait() {
+        use std::{
+            future::Future,
+            pin::Pin,
+            task::{Context, Waker},
+        };
+        let waker = Waker::from(Arc::new(NoopWaker));
+        let mut cx = Context::from_waker(&waker);
+        let (mut input, mut output) = TripleBuffer::new(&0).split();
+        let waiting_bits =
+            BackBufferInfo::PRODUCER_WAITING_BIT | BackBufferInfo::CONSUMER_WAITING_BIT;
+        let back_bits = |input: &Input<i32>| input.shared.back_info.load(Ordering::Relaxed).0;
+
+        // Pending consumer
+        let mut changed = output.changed();
+        assert!(Pin::new(&mut changed).poll(&mut cx).is_pending());
+        assert_ne!(back_bits(&input) & waiting_bits, 0);
+        drop(changed);
+        assert_eq!(back_bits(&input) & waiting_bits, 0);
+        assert!(input.shared.consumer_waker.lock().unwrap().is_none());
+
+        // Pending producer
+        let mut consumed = input.write_tracked(1);
+        assert!(Pin::new(&mut consumed).poll(&mut cx).is_pending());
+        drop(consumed);
+        assert_eq!(back_bits(&input) & waiting_bits, 0);
+        assert!(input.shared.producer_waker.lock().unwrap().is_none());
+
+        // The reassembled triple buffer is still valid
+        let buf = TripleBuffer::reunite(input, output).unwrap();
+        assert_eq!(buf.validate(), Ok(()));
+    }
+
+    /// Check that wakers which poll the task right away do not deadlock
+    #[test]
+    #[cfg(feature = "async")]
+    fn inline_wake() {
+        use std::{
+            future::Future,
+            pin::Pin,
+            sync::Mutex,
+            task::{Context, Wake, Waker},
+        };
+
+        /// Task which is polled by its own waker, like in some executors
+        struct InlineTask(Mutex<Option<Pin<Box<dyn Future<Output = ()> + Send>>>>);
+        //
+        impl Wake for InlineTask {
+            fn wake(self: Arc<Self>) {
+                let waker = Waker::from(self.clone());
+                let mut future = self.0.lock().unwrap();
+                if let Some(pending) = future.as_mut() {
+                    let mut cx = Context::from_waker(&waker);
+                    if pending.as_mut().poll(&mut cx).is_ready() {
+                        *future = None;
+                    }
+                }
+            }
+        }
+
+        // The task waits for several updates, so when it is polled from
+        // within publish(), it must register itself with the producer again
+        let (mut input, mut output) = TripleBuffer::new(&0).split();
+        let task = Arc::new(InlineTask(Mutex::new(Some(Box::pin(async move {
+            while output.changed().await && *output.read() < 2 {}
+        })))));
+        Waker::from(task.clone()).wake();
+        input.write(1);
+        assert!(task.0.lock().unwrap().is_some());
+        input.write(2);
+        assert!(task.0.lock().unwrap().is_none());
+    }
+
+    /// Check that consumer threads can block until an update is published
+    #[test]
+    #[cfg(feature = "blocking")]
+    fn wait_and_read() {
+        // Updates which are already available are fetched right away
+        let (mut input, mut output) = TripleBuffer::new(&0).split();
+        input.write(1);
+        assert_eq!(*output.wait_and_read(), 1);
+
+        // Otherwise, the producer wakes up the consumer thread
+        let producer = thread::spawn(move || {
+            thread::sleep(Duration::from_millis(10));
+            input.write(2);
+            input
+        });
+        assert_eq!(*output.wait_and_read(), 2);
+        let input = producer.join().unwrap();
+
+        // The consumer is also woken up if the producer goes away
+        let producer = thread::spawn(move || {
+            thread::sleep(Duration::from_millis(10));
+            drop(input);
+        });
+        assert_eq!(*output.wait_and_read(), 2);
+        assert!(!output.writer_alive());
+        producer.join().unwrap();
+    }
+
+    /// Check that triple buffer halves can be identified
+    #[test]
+    fn identity() {
+        let (input1, output1) = TripleBuffer::new(&0).split();
+        let (input2, output2) = TripleBuffer::new(&0).split();
+        assert!(input1.same_buffer(&input1));
+        assert!(!input1.same_buffer(&input2));
+        assert!(output1.same_buffer(&output1));
+        assert!(!output1.same_buffer(&output2));
+        assert!(input1.is_paired_with(&output1));
+        assert!(output2.is_paired_with(&input2));
+        assert!(!input1.is_paired_with(&output2));
+        assert!(!output1.is_paired_with(&input2));
+    }
+
+    /// Check that spawn() runs both halves on named threads
+    #[test]
+    #[cfg(feature = "std")]
+    fn spawn() {
+        let thread_name = || thread::current().name().map(String::from);
+        let mut buf = TripleBuffer::new(&0);
+        buf.set_label("spawned");
+        let (producer, consumer) = buf
+            .spawn(
+                move |mut input| {
+                    input.write(42);
+                    thread_name()
+                },
+                move |mut output| {
+                    while !output.updated() {
+                        thread::yield_now();
+                    }
+                    (*output.read(), thread_name())
+                },
+            )
+            .unwrap();
+        assert_eq!(
+            producer.join().unwrap().as_deref(),
+            Some("spawned producer")
+        );
+        let (value, name) = consumer.join().unwrap();
+        assert_eq!(value, 42);
+        assert_eq!(name.as_deref(), Some("spawned consumer"));
+
+        let (producer, _consumer) = TripleBuffer::new(&0)
+            .spawn(move |_| thread_name(), |_| ())
+            .unwrap();
+        assert_eq!(
+            producer.join().unwrap().as_deref(),
+            Some("triple buffer producer")
+        );
+    }
 
-        // Check if an update is present in the back-buffer
-        let updated = self.updated();
-        if updated {
-            // If so, exchange our output buffer with the back-buffer, thusly
-            // acquiring exclusive access to the old back buffer while giving
-            // the producer a new back-buffer to write to.
-            //
-            // The ordering must be AcqRel, because...
-            //
-            // - Our accesses to the previous buffer must not be reordered after
-            //   this operation (which mandates Release ordering), otherwise
-            //   they could race with the producer accessing the freshly
-            //   liberated buffer.
-            // - Our accesses from the buffer must not be reordered before this
-            //   operation (which mandates Consume ordering, that is best
-            //   approximated by Acquire in Rust), otherwise they would race
-            //   with the producer writing into the buffer before publishing it.
-            //   * This reordering may seem paradoxical, but could happen if the
-            //     compiler or CPU correctly speculated the new buffer's index
-            //     before that index is actually read, as well as on weird hardware
-            //     like GPUs where CPU caches require manual synchronization.
-            //
-            let former_back_info = shared_state
-                .back_info
-                .swap(self.output_idx, Ordering::AcqRel);
+    /// Check that split triple buffers can be reassembled
+    #[test]
+    fn reunite() {
+        let (input1, output1) = TripleBuffer::new(&1).split();
+        let (input2, output2) = TripleBuffer::new(&2).split();
+
+        // Halves from different triple buffers are handed back
+        let (input1, output2) = TripleBuffer::reunite(input1, output2).unwrap_err();
+        assert!(input1.is_paired_with(&output1));
+        assert!(input2.is_paired_with(&output2));
 
-            // Make the old back-buffer our new output buffer
-            self.output_idx = former_back_info & BACK_INDEX_MASK;
+        // Halves from the same triple buffer are reassembled
+        let mut buf = TripleBuffer::reunite(input1, output1).unwrap();
+        buf.input.write(3);
+        buf.reinitialize(&4);
+        assert_eq!(*buf.output.read(), 4);
+        assert!(TripleBuffer::reunite(input2, output2).is_ok());
+    }
+
+    /// Check that both halves can be borrowed at the same time
+    #[test]
+    fn split_mut() {
+        let mut buf = TripleBuffer::new(&0);
+        {
+            let (input, output) = buf.split_mut();
+            for value in 1..5 {
+                input.write(value);
+                assert_eq!(*output.read(), value);
+            }
+            input.write(5);
         }
+        assert_eq!(buf.validate(), Ok(()));
+        assert_eq!(*buf.output.read(), 5);
 
-        // Tell whether an update was carried out
-        updated
+        // Methods which need exclusive access to the shared state detect that
+        // the halves of two triple buffers were mixed up
+        let mut other = TripleBuffer::new(&0);
+        core::mem::swap(buf.split_mut().0, other.split_mut().0);
+        assert!(buf.validate().is_err());
+        let result = panic::catch_unwind(AssertUnwindSafe(|| buf.set_label("mixed")));
+        assert!(result.is_err());
     }
-}
 
-/// Triple buffer shared state
-///
-/// In a triple buffering communication protocol, the producer and consumer
-/// share the following storage:
-///
-/// - Three memory buffers suitable for storing the data at hand
-/// - Information about the back-buffer: which buffer is the current back-buffer
-///   and whether an update was published since the last readout.
-///
-#[derive(Debug)]
-struct SharedState<T: Send> {
-    /// Data storage buffers
-    buffers: [CachePadded<UnsafeCell<T>>; 3],
+    /// Check that triple buffers of Arcs reuse allocations when possible
+    #[test]
+    fn arc_payload() {
+        let mut buf = TripleBuffer::new(&Arc::new(0));
 
-    /// Information about the current back-buffer state
-    back_info: CachePadded<AtomicBackBufferInfo>,
-}
-//
-#[doc(hidden)]
-impl<T: Send> SharedState<T> {
-    /// Given (a way to generate) buffer contents and the back info, build the shared state
-    fn new(mut gen_buf_data: impl FnMut(usize) -> T, back_info: BackBufferInfo) -> Self {
-        let mut make_buf = |i| -> CachePadded<UnsafeCell<T>> {
-            CachePadded::new(UnsafeCell::new(gen_buf_data(i)))
-        };
-        Self {
-            buffers: [make_buf(0), make_buf(1), make_buf(2)],
-            back_info: CachePadded::new(AtomicBackBufferInfo::new(back_info)),
+        // Versions held by the consumer are not modified by the producer
+        buf.input.write_arc(1);
+        let first = buf.output.read_arc();
+        for value in 2..10 {
+            buf.input.write_arc(value);
         }
-    }
-}
-//
-#[doc(hidden)]
-impl<T: Clone + Send> SharedState<T> {
-    /// Cloning the shared state is unsafe because you must ensure that no one
-    /// is concurrently accessing it, since &self is enough for writing.
-    unsafe fn clone(&self) -> Self {
-        Self::new(
-            |i| (*self.buffers[i].get()).clone(),
-            self.back_info.load(Ordering::Relaxed),
-        )
-    }
-}
-//
-#[doc(hidden)]
-impl<T: PartialEq + Send> SharedState<T> {
-    /// Equality is unsafe for the same reason as cloning: you must ensure that
-    /// no one is concurrently accessing the triple buffer to avoid data races.
-    unsafe fn eq(&self, other: &Self) -> bool {
-        // Check whether the contents of all buffers are equal...
-        let buffers_equal = self
-            .buffers
-            .iter()
-            .zip(other.buffers.iter())
-            .all(|tuple| -> bool {
-                let (cell1, cell2) = tuple;
-                *cell1.get() == *cell2.get()
-            });
+        assert_eq!(*first, 1);
+        assert_eq!(*buf.output.read_arc(), 9);
 
-        // ...then check whether the rest of the shared state is equal
-        buffers_equal
-            && (self.back_info.load(Ordering::Relaxed) == other.back_info.load(Ordering::Relaxed))
+        // Otherwise, allocations are reused
+        let input_ptr = Arc::as_ptr(buf.input.input_buffer());
+        buf.input.write_arc(10);
+        assert_eq!(Arc::as_ptr(buf.output.read()), input_ptr);
     }
-}
-//
-unsafe impl<T: Send> Sync for SharedState<T> {}
 
-// Index types used for triple buffering
-//
-// These types are used to index into triple buffers. In addition, the
-// BackBufferInfo type is actually a bitfield, whose third bit (numerical
-// value: 4) is set to 1 to indicate that the producer published an update into
-// the back-buffer, and reset to 0 when the consumer fetches the update.
-//
-type BufferIndex = u8;
-type BackBufferInfo = BufferIndex;
-//
-type AtomicBackBufferInfo = AtomicU8;
-const BACK_INDEX_MASK: u8 = 0b11; // Mask used to extract back-buffer index
-const BACK_DIRTY_BIT: u8 = 0b100; // Bit set by producer to signal updates
+    /// Check that event hooks are called when expected
+    #[test]
+    fn event_hooks() {
+        thread_local! {
+            static EVENTS: Cell<(u32, u32)> = const { Cell::new((0, 0)) };
+        }
+        fn events() -> (u32, u32) {
+            EVENTS.with(Cell::get)
+        }
 
-/// Unit tests
-#[cfg(test)]
-mod tests {
-    use super::{BufferIndex, SharedState, TripleBuffer, BACK_DIRTY_BIT, BACK_INDEX_MASK};
-    use std::{fmt::Debug, ops::Deref, sync::atomic::Ordering, thread, time::Duration};
-    use testbench::race_cell::{RaceCell, Racey};
+        let mut buf = TripleBuffer::new(&0);
+        buf.on_publish(|| EVENTS.with(|e| e.set((e.get().0 + 1, e.get().1))));
+        buf.on_consume(|| EVENTS.with(|e| e.set((e.get().0, e.get().1 + 1))));
+        let mut buf = buf.clone();
+        buf.output.update();
+        assert_eq!(events(), (0, 0));
+        buf.input.write(1);
+        buf.input.write(2);
+        assert_eq!(events(), (2, 0));
+        buf.output.update();
+        buf.output.update();
+        assert_eq!(events(), (2, 1));
+    }
 
-    /// Check that triple buffers are properly initialized
+    /// Check that labels are visible from both ends of the triple buffer
     #[test]
-    fn initial_state() {
-        // Let's create a triple buffer
-        let mut buf = TripleBuffer::new(&42);
-        check_buf_state(&mut buf, false);
-        assert_eq!(*buf.output.read(), 42);
+    fn label() {
+        let mut buf = TripleBuffer::new(&0);
+        assert_eq!(buf.input.label(), None);
+        assert_eq!(buf.output.label(), None);
+
+        buf.set_label("audio");
+        assert_eq!(buf.clone().output.label(), Some("audio"));
+        let (input, output) = buf.split();
+        assert_eq!(input.label(), Some("audio"));
+        assert_eq!(output.label(), Some("audio"));
+        assert!(format!("{input:?}").contains("\"audio\""));
     }
 
-    /// Check that the shared state's unsafe equality operator works
+    /// Check that updates are not published twice by retrying producers
     #[test]
-    fn partial_eq_shared() {
-        // Let's create some dummy shared state
-        let dummy_state = SharedState::<u16>::new(|i| [111, 222, 333][i], 0b10);
+    fn publish_if_unchanged() {
+        let mut buf = TripleBuffer::new(&0);
+        let initial = buf.input.sequence();
+        assert_eq!(initial, buf.output.sequence());
 
-        // Check that the dummy state is equal to itself
-        assert!(unsafe { dummy_state.eq(&dummy_state) });
+        // The first attempt goes through...
+        *buf.input.input_buffer() = 1;
+        let first = buf.input.publish_if_unchanged(initial).unwrap();
+        assert!(first > initial);
+        assert_eq!(buf.input.sequence(), first);
 
-        // Check that it's not equal to a state where buffer contents differ
-        assert!(unsafe { !dummy_state.eq(&SharedState::<u16>::new(|i| [114, 222, 333][i], 0b10)) });
-        assert!(unsafe { !dummy_state.eq(&SharedState::<u16>::new(|i| [111, 225, 333][i], 0b10)) });
-        assert!(unsafe { !dummy_state.eq(&SharedState::<u16>::new(|i| [111, 222, 336][i], 0b10)) });
+        // ...but retries do not
+        *buf.input.input_buffer() = 2;
+        assert_eq!(buf.input.publish_if_unchanged(initial), Err(first));
+        assert_eq!(buf.input.sequence(), first);
+        assert_eq!(buf.output.read_with_sequence(), (&1, first));
+        assert!(!buf.output.update());
 
-        // Check that it's not equal to a state where the back info differs
-        assert!(unsafe {
-            !dummy_state.eq(&SharedState::<u16>::new(
-                |i| [111, 222, 333][i],
-                BACK_DIRTY_BIT & 0b10,
-            ))
-        });
-        assert!(unsafe { !dummy_state.eq(&SharedState::<u16>::new(|i| [111, 222, 333][i], 0b01)) });
+        // Newer tokens can be used for subsequent updates
+        let second = buf.input.publish_if_unchanged(first).unwrap();
+        assert!(second > first);
+        assert_eq!(buf.output.read_with_sequence(), (&2, second));
     }
 
-    /// Check that TripleBuffer's PartialEq impl works
+    /// Check that the producer keeps track of the amount of published data
     #[test]
-    fn partial_eq() {
-        // Create a triple buffer
-        let buf = TripleBuffer::new(&"test");
+    #[cfg(feature = "stats")]
+    fn bytes_published() {
+        let mut buf = TripleBuffer::new(&0u32);
+        assert_eq!(buf.input.bytes_published(), 0);
+        buf.input.write(1);
+        buf.input.write(2);
+        assert_eq!(buf.input.bytes_published(), 8);
 
-        // Check that it is equal to itself
-        assert_eq!(buf, buf);
+        let mut buf = TripleBuffer::new(&String::new());
+        buf.input.measure_bytes_with(String::len);
+        buf.input.write("Hello".to_owned());
+        buf.input.write("world!".to_owned());
+        assert_eq!(buf.input.bytes_published(), 11);
+    }
 
-        // Make another buffer with different contents. As buffer creation is
-        // deterministic, this should only have an impact on the shared state,
-        // but the buffers should nevertheless be considered different.
-        let buf2 = TripleBuffer::new(&"taste");
-        assert_eq!(buf.input.input_idx, buf2.input.input_idx);
-        assert_eq!(buf.output.output_idx, buf2.output.output_idx);
-        assert!(buf != buf2);
+    /// Check that the drop callback receives every buffer exactly once
+    #[test]
+    fn on_drop() {
+        let dropped = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let mut buf = TripleBuffer::new(&0);
+        let dropped2 = dropped.clone();
+        buf.on_drop(move |value| dropped2.lock().unwrap().push(value));
+        let (mut input, output) = buf.split();
+        input.write(1);
+        input.write(2);
+        drop(output);
+        assert!(dropped.lock().unwrap().is_empty());
+        drop(input);
 
-        // Check that changing either the input or output buffer index will
-        // also lead two TripleBuffers to be considered different (this test
-        // technically creates an invalid TripleBuffer state, but it's the only
-        // way to check that the PartialEq impl is exhaustive)
-        let mut buf3 = TripleBuffer::new(&"test");
-        assert_eq!(buf, buf3);
-        let old_input_idx = buf3.input.input_idx;
-        buf3.input.input_idx = buf3.output.output_idx;
-        assert!(buf != buf3);
-        buf3.input.input_idx = old_input_idx;
-        buf3.output.output_idx = old_input_idx;
-        assert!(buf != buf3);
+        let mut dropped = dropped.lock().unwrap().clone();
+        dropped.sort_unstable();
+        assert_eq!(dropped, [0, 1, 2]);
     }
 
-    /// Check that the shared state's unsafe clone operator works
+    /// Check that the buffer contents can be extracted
     #[test]
-    fn clone_shared() {
-        // Let's create some dummy shared state
-        let dummy_state = SharedState::<u8>::new(|i| [123, 231, 132][i], BACK_DIRTY_BIT & 0b01);
+    fn into_inner() {
+        let dropped = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let mut buf = TripleBuffer::new(&0);
+        let dropped2 = dropped.clone();
+        buf.on_drop(move |value| dropped2.lock().unwrap().push(value));
+        buf.input.write(1);
+        buf.output.read();
+        buf.input.write(2);
 
-        // Now, try to clone it
-        let dummy_state_copy = unsafe { dummy_state.clone() };
+        let mut values = buf.into_inner();
+        values.sort_unstable();
+        assert_eq!(values, [0, 1, 2]);
+        assert!(dropped.lock().unwrap().is_empty());
+    }
 
-        // Check that the contents of the original state did not change
-        assert!(unsafe {
-            dummy_state.eq(&SharedState::<u8>::new(
-                |i| [123, 231, 132][i],
-                BACK_DIRTY_BIT & 0b01,
-            ))
+    /// Check that bounded-loss writes block once the overwrite budget is spent
+    #[test]
+    #[cfg(feature = "blocking")]
+    fn write_bounded_loss() {
+        // Within the overwrite budget, writes do not block
+        let mut buf = TripleBuffer::new(&0);
+        buf.input.write_bounded_loss(1, 2);
+        buf.input.write_bounded_loss(2, 2);
+        buf.input.write_bounded_loss(3, 2);
+        assert_eq!(buf.input.overwrite_streak, 2);
+
+        // Reading resets the overwrite streak
+        assert_eq!(*buf.output.read(), 3);
+        buf.input.write_bounded_loss(4, 2);
+        assert_eq!(buf.input.overwrite_streak, 0);
+
+        // Past the budget, the producer waits for the consumer
+        let (mut input, mut output) = TripleBuffer::new(&0).split();
+        let producer = thread::spawn(move || {
+            for value in 1..=100 {
+                input.write_bounded_loss(value, 0);
+            }
+            input
         });
+        let mut last_value = 0;
+        while last_value < 100 {
+            if output.update() {
+                let value = *output.peek_output_buffer();
+                assert_eq!(value, last_value + 1);
+                last_value = value;
+            }
+        }
+        let mut input = producer.join().unwrap();
 
-        // Check that the contents of the original and final state are identical
-        assert!(unsafe { dummy_state.eq(&dummy_state_copy) });
+        // The consumer can tell when the producer is waiting for it
+        assert!(!output.producer_waiting());
+        let producer = thread::spawn(move || {
+            input.write_bounded_loss(101, 0);
+            input.write_bounded_loss(102, 0);
+            input
+        });
+        while !output.producer_waiting() {
+            thread::yield_now();
+        }
+        assert_eq!(*output.read(), 101);
+        let mut input = producer.join().unwrap();
+        assert!(!output.producer_waiting());
+        assert_eq!(*output.read(), 102);
+
+        // Once the consumer is gone, the producer does not block anymore
+        drop(output);
+        input.write_bounded_loss(103, 0);
+        input.write_bounded_loss(104, 0);
     }
 
-    /// Check that TripleBuffer's Clone impl works
+    /// Check that bounded-loss writes can give up waiting for the consumer
     #[test]
-    fn clone() {
-        // Create a triple buffer
-        let mut buf = TripleBuffer::new(&4.2);
+    #[cfg(feature = "blocking")]
+    fn write_bounded_loss_timeout() {
+        // Within the overwrite budget, writes do not wait
+        let mut buf = TripleBuffer::new(&0);
+        let timeout = Duration::from_millis(10);
+        assert_eq!(buf.input.write_bounded_loss_timeout(1, 1, timeout), Ok(()));
+        assert_eq!(buf.input.write_bounded_loss_timeout(2, 1, timeout), Ok(()));
+        assert_eq!(buf.input.overwrite_streak, 1);
 
-        // Put it in a nontrivial state
-        unsafe {
-            *buf.input.shared.buffers[0].get() = 1.2;
-            *buf.input.shared.buffers[1].get() = 3.4;
-            *buf.input.shared.buffers[2].get() = 5.6;
-        }
-        buf.input
-            .shared
-            .back_info
-            .store(BACK_DIRTY_BIT & 0b01, Ordering::Relaxed);
-        buf.input.input_idx = 0b10;
-        buf.output.output_idx = 0b00;
+        // Past the budget, a consumer on the same thread cannot catch up, so
+        // the write times out and nothing is published
+        assert_eq!(buf.input.write_bounded_loss_timeout(3, 1, timeout), Err(3));
+        assert_eq!(buf.input.overwrite_streak, 1);
+        assert!(!buf.output.producer_waiting());
+        assert!(buf.input.shared.producer_waker.lock().unwrap().is_none());
+        assert_eq!(buf.validate(), Ok(()));
 
-        // Now clone it
-        let buf_clone = buf.clone();
+        // Once the consumer catches up, writes go through again
+        assert_eq!(*buf.output.read(), 2);
+        assert_eq!(buf.input.write_bounded_loss_timeout(3, 1, timeout), Ok(()));
+        assert_eq!(buf.input.overwrite_streak, 0);
+        assert_eq!(*buf.output.read(), 3);
+    }
 
-        // Check that the clone uses its own, separate shared data storage
-        assert_eq!(
-            as_ptr(&buf_clone.output.shared),
-            as_ptr(&buf_clone.output.shared)
-        );
-        assert!(as_ptr(&buf_clone.input.shared) != as_ptr(&buf.input.shared));
+    /// Check that lazy writes are skipped when the consumer lags behind
+    #[test]
+    fn lazy_write() {
+        let mut buf = TripleBuffer::new(&0);
+        assert!(buf.input.write_lazy(|| 1));
 
-        // Check that it is identical from PartialEq's point of view
-        assert_eq!(buf, buf_clone);
+        // The last update was not consumed, so the value is not computed
+        assert!(!buf.input.write_lazy(|| unreachable!()));
+        assert_eq!(*buf.output.read(), 1);
 
-        // Check that the contents of the original buffer did not change
-        unsafe {
-            assert_eq!(*buf.input.shared.buffers[0].get(), 1.2);
-            assert_eq!(*buf.input.shared.buffers[1].get(), 3.4);
-            assert_eq!(*buf.input.shared.buffers[2].get(), 5.6);
-        }
-        assert_eq!(
-            buf.input.shared.back_info.load(Ordering::Relaxed),
-            BACK_DIRTY_BIT & 0b01
-        );
-        assert_eq!(buf.input.input_idx, 0b10);
-        assert_eq!(buf.output.output_idx, 0b00);
+        // Once it is consumed, writing works again
+        assert!(buf.input.write_lazy(|| 2));
+        assert_eq!(*buf.output.read(), 2);
     }
 
-    /// Check that the low-level publish/update primitives work
+    /// Check that the producer can tell how often the consumer reads
     #[test]
-    fn swaps() {
-        // Create a new buffer, and a way to track any changes to it
-        let mut buf = TripleBuffer::new(&[123, 456]);
-        let old_buf = buf.clone();
-        let old_input_idx = old_buf.input.input_idx;
-        let old_shared = &old_buf.input.shared;
-        let old_back_info = old_shared.back_info.load(Ordering::Relaxed);
-        let old_back_idx = old_back_info & BACK_INDEX_MASK;
-        let old_output_idx = old_buf.output.output_idx;
+    #[cfg(feature = "stats")]
+    fn reads_since_last_write() {
+        let mut buf = TripleBuffer::new(&0);
+        assert_eq!(buf.input.reads_since_last_write(), 0);
 
-        // Check that updating from a clean state works
-        assert!(!buf.output.update());
-        assert_eq!(buf, old_buf);
-        check_buf_state(&mut buf, false);
+        // Reads count whether they fetch an update or not
+        buf.output.update();
+        assert_eq!(buf.input.reads_since_last_write(), 1);
+        buf.input.write(1);
+        assert_eq!(buf.input.reads_since_last_write(), 0);
+        assert_eq!(*buf.output.read(), 1);
+        assert_eq!(*buf.output.read(), 1);
+        assert_eq!(buf.input.reads_since_last_write(), 2);
 
-        // Check that publishing from a clean state works
-        assert!(!buf.input.publish());
-        let mut expected_buf = old_buf.clone();
-        expected_buf.input.input_idx = old_back_idx;
-        expected_buf
-            .input
-            .shared
-            .back_info
-            .store(old_input_idx | BACK_DIRTY_BIT, Ordering::Relaxed);
-        assert_eq!(buf, expected_buf);
-        check_buf_state(&mut buf, true);
+        // Peeking at the output buffer does not count as a read
+        buf.input.write(2);
+        buf.output.peek_output_buffer();
+        assert_eq!(buf.input.reads_since_last_write(), 0);
+    }
 
-        // Check that overwriting a dirty state works
-        assert!(buf.input.publish());
-        let mut expected_buf = old_buf.clone();
-        expected_buf.input.input_idx = old_input_idx;
-        expected_buf
-            .input
-            .shared
-            .back_info
-            .store(old_back_idx | BACK_DIRTY_BIT, Ordering::Relaxed);
-        assert_eq!(buf, expected_buf);
-        check_buf_state(&mut buf, true);
+    /// Check that fetched updates get their latency recorded
+    #[test]
+    #[cfg(feature = "latency-stats")]
+    fn latency_stats() {
+        let mut buf = TripleBuffer::new(&0);
 
-        // Check that updating from a dirty state works
+        // The initial value was never published, so it has no latency
+        assert!(!buf.output.update());
+        assert_eq!(buf.output.latency_histogram().count(), 0);
+
+        // Overwritten updates never reach the consumer, so they are not counted
+        buf.input.write(1);
+        buf.input.write(2);
+        thread::sleep(Duration::from_millis(1));
         assert!(buf.output.update());
-        expected_buf.output.output_idx = old_back_idx;
-        expected_buf
-            .output
-            .shared
-            .back_info
-            .store(old_output_idx, Ordering::Relaxed);
-        assert_eq!(buf, expected_buf);
-        check_buf_state(&mut buf, false);
+        let histogram = buf.output.latency_histogram();
+        assert_eq!(histogram.count(), 1);
+        assert!(histogram.max().unwrap() >= Duration::from_millis(1));
+
+        // The histogram can be taken out to start over
+        buf.input.write(3);
+        assert_eq!(*buf.output.read(), 3);
+        assert_eq!(buf.output.take_latency_histogram().count(), 2);
+        assert_eq!(buf.output.latency_histogram().count(), 0);
+    }
+
+    /// Check that the time between reads is measured
+    #[test]
+    #[cfg(feature = "latency-stats")]
+    fn read_interval_stats() {
+        let mut buf = TripleBuffer::new(&0);
+        buf.output.read();
+        assert_eq!(buf.output.read_interval_stats().count(), 0);
+
+        // Every check for updates counts, whether it fetched an update or not
+        thread::sleep(Duration::from_millis(1));
+        buf.output.update();
+        buf.input.write(1);
+        buf.output.read();
+        let stats = buf.output.read_interval_stats();
+        assert_eq!(stats.count(), 2);
+        assert!(stats.max().unwrap() >= Duration::from_millis(1));
+        assert!(stats.min().unwrap() <= stats.mean().unwrap());
+
+        // The statistics can be taken out to start over
+        assert_eq!(buf.output.take_read_interval_stats(), stats);
+        assert_eq!(buf.output.read_interval_stats().count(), 0);
+        buf.output.read();
+        assert_eq!(buf.output.read_interval_stats().count(), 1);
     }
 
     /// Check that (sequentially) writing to a triple buffer works
@@ -810,6 +4626,64 @@ mod tests {
         );
     }
 
+    /// Check that publishing makes all prior writes to the buffer visible
+    ///
+    /// The producer updates the fields of a multi-field struct one by one,
+    /// using plain non-atomic writes, then publishes it. The consumer must
+    /// never observe a torn struct, which would mean that the back-buffer
+    /// swap only synchronized the buffer index, not the buffer contents. When
+    /// run under Miri, any missing happens-before relationship between these
+    /// writes and the consumer's reads will also be reported as a data race.
+    #[test]
+    #[ignore]
+    fn publish_happens_before_read() {
+        // We will stress the infrastructure by performing this many writes
+        // as a reader continuously reads the latest value
+        #[cfg(not(feature = "miri"))]
+        const TEST_WRITE_COUNT: usize = 10_000_000;
+        #[cfg(feature = "miri")]
+        const TEST_WRITE_COUNT: usize = 1_000;
+
+        // Multi-field payload whose fields must all be equal once published
+        #[derive(Clone, Default)]
+        struct Fields {
+            first: usize,
+            middle: [usize; 6],
+            last: usize,
+        }
+
+        // This is the buffer that our reader and writer will share
+        let buf = TripleBuffer::new(&Fields::default());
+        let (mut buf_input, mut buf_output) = buf.split();
+
+        // Concurrently run a writer which updates the fields in order, and a
+        // reader which checks that every published struct is consistent.
+        testbench::concurrent_test_2(
+            move || {
+                for value in 1..=TEST_WRITE_COUNT {
+                    let fields = buf_input.input_buffer();
+                    fields.first = value;
+                    for field in fields.middle.iter_mut() {
+                        *field = value;
+                    }
+                    fields.last = value;
+                    buf_input.publish();
+                }
+            },
+            move || {
+                let mut last_value = 0;
+                while last_value < TEST_WRITE_COUNT {
+                    let fields = buf_output.read();
+                    let value = fields.first;
+                    assert!(fields.middle.iter().all(|&field| field == value));
+                    assert_eq!(fields.last, value);
+                    assert!(value >= last_value);
+                    last_value = value;
+                }
+            },
+        );
+    }
+
     /// Check that uncontended concurrent reads and writes work
     ///
     /// **WARNING:** This test unfortunately needs to have timing-dependent
@@ -917,9 +4791,18 @@ mod tests {
     }
 
     /// Range check for triple buffer indexes
-    #[allow(unused_comparisons)]
     fn index_in_range(idx: BufferIndex) -> bool {
-        (0..=2).contains(&idx)
+        BufferIndex::new(idx.0).is_some()
+    }
+
+    /// Build back-buffer information for a valid back-buffer index
+    fn back_info(idx: u8, dirty: bool) -> BackBufferInfo {
+        let back_info = BackBufferInfo::clean(BufferIndex::new(idx).unwrap());
+        if dirty {
+            back_info.with_dirty()
+        } else {
+            back_info
+        }
     }
 
     /// Get a pointer to the target of some reference (e.g. an &, an Arc...)
@@ -930,7 +4813,7 @@ mod tests {
     /// Check the state of a buffer, and the effect of queries on it
     fn check_buf_state<T>(buf: &mut TripleBuffer<T>, expected_dirty_bit: bool)
     where
-        T: Clone + Debug + PartialEq + Send,
+        T: Clone + Debug + PartialEq + Send + Sync,
     {
         // Make a backup of the buffer's initial state
         let initial_buf = buf.clone();
@@ -940,8 +4823,8 @@ mod tests {
 
         // Access the shared state and decode back-buffer information
         let back_info = buf.input.shared.back_info.load(Ordering::Relaxed);
-        let back_idx = back_info & BACK_INDEX_MASK;
-        let back_buffer_dirty = back_info & BACK_DIRTY_BIT != 0;
+        let back_idx = back_info.index();
+        let back_buffer_dirty = back_info.is_dirty();
 
         // Input-/output-/back-buffer indexes must be in range
         assert!(index_in_range(buf.input.input_idx));
@@ -956,10 +4839,24 @@ mod tests {
         // Back-buffer must have the expected dirty bit
         assert_eq!(back_buffer_dirty, expected_dirty_bit);
 
+        // The invariant checker must agree with the above
+        assert_eq!(buf.validate(), Ok(()));
+
         // Check that the "input buffer" query behaves as expected
         assert_eq!(
             as_ptr(&buf.input.input_buffer()),
-            buf.input.shared.buffers[buf.input.input_idx as usize].get()
+            buf.input.shared.buffers[buf.input.input_idx.get()]
+                .data
+                .get()
+        );
+        assert_eq!(*buf, initial_buf);
+
+        // Check that the "peek input buffer" query behaves as expected
+        assert_eq!(
+            as_ptr(&buf.input.peek_input_buffer()),
+            buf.input.shared.buffers[buf.input.input_idx.get()]
+                .data
+                .get()
         );
         assert_eq!(*buf, initial_buf);
 
@@ -970,7 +4867,9 @@ mod tests {
         // Check that the output_buffer query works in the initial state
         assert_eq!(
             as_ptr(&buf.output.output_buffer()),
-            buf.output.shared.buffers[buf.output.output_idx as usize].get()
+            buf.output.shared.buffers[buf.output.output_idx.get()]
+                .data
+                .get()
         );
         assert_eq!(*buf, initial_buf);
 
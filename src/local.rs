@@ -0,0 +1,241 @@
+//! Single-threaded triple buffer, for payloads which are not `Send`
+//!
+//! Sometimes, the producer and consumer of data live on the same thread, e.g.
+//! when they are different states of a coroutine-like state machine. In this
+//! case, the latest-value semantics and the in-place buffer reuse of triple
+//! buffering can still be useful, but the thread-safety machinery is not
+//! needed, and its `T: Send` bound gets in the way of using `Rc` and friends.
+
+use crate::{
+    atomic::{self, BackInfoAtomic, LocalCell},
+    BackBufferInfo, BufferIndex,
+};
+use alloc::rc::Rc;
+use core::{cell::UnsafeCell, sync::atomic::Ordering};
+
+/// Single-threaded triple buffer
+///
+/// This works like `TripleBuffer`, but the input and output interfaces cannot
+/// be sent to other threads. In exchange, the payload does not need to be
+/// `Send`, and no atomic operations are used.
+///
+/// ```
+/// # use std::rc::Rc;
+/// # use triple_buffer::LocalTripleBuffer;
+/// let (mut input, mut output) = LocalTripleBuffer::new(&Rc::new(0)).split();
+/// input.write(Rc::new(42));
+/// assert_eq!(**output.read(), 42);
+/// ```
+///
+#[derive(Debug)]
+pub struct LocalTripleBuffer<T> {
+    /// Input object used by producers to send updates
+    input: LocalInput<T>,
+
+    /// Output object used by consumers to read the current value
+    output: LocalOutput<T>,
+}
+//
+impl<T: Clone> LocalTripleBuffer<T> {
+    /// Construct a single-threaded triple buffer with a certain initial value
+    pub fn new(initial: &T) -> Self {
+        Self::from_fn(|| initial.clone())
+    }
+}
+//
+impl<T: Default> Default for LocalTripleBuffer<T> {
+    fn default() -> Self {
+        Self::from_fn(T::default)
+    }
+}
+//
+impl<T> LocalTripleBuffer<T> {
+    /// Construct a single-threaded triple buffer, using a function to generate
+    /// initial values
+    pub fn from_fn(mut generator: impl FnMut() -> T) -> Self {
+        // Start with the shared state, using the same initial buffer indices
+        // as a thread-safe triple buffer...
+        let shared = Rc::new(LocalSharedState {
+            buffers: [
+                UnsafeCell::new(generator()),
+                UnsafeCell::new(generator()),
+                UnsafeCell::new(generator()),
+            ],
+            back_info: LocalCell::new(BackBufferInfo::clean(BufferIndex::from_const(0))),
+        });
+
+        // ...then construct the input and output structs
+        Self {
+            input: LocalInput {
+                shared: shared.clone(),
+                input_idx: BufferIndex::from_const(1),
+            },
+            output: LocalOutput {
+                shared,
+                output_idx: BufferIndex::from_const(2),
+            },
+        }
+    }
+
+    /// Extract input and output of the triple buffer
+    pub fn split(self) -> (LocalInput<T>, LocalOutput<T>) {
+        (self.input, self.output)
+    }
+}
+
+/// Producer interface to the single-threaded triple buffer
+#[derive(Debug)]
+pub struct LocalInput<T> {
+    /// Reference-counted shared state
+    shared: Rc<LocalSharedState<T>>,
+
+    /// Index of the input buffer (which is private to the producer)
+    input_idx: BufferIndex,
+}
+//
+impl<T> LocalInput<T> {
+    /// Write a new value into the triple buffer
+    pub fn write(&mut self, value: T) {
+        *self.input_buffer() = value;
+        self.publish();
+    }
+
+    /// Check if the consumer has fetched our last submission yet
+    pub fn consumed(&self) -> bool {
+        !self.shared.back_info.load(Ordering::Relaxed).is_dirty()
+    }
+
+    /// Access the input buffer directly
+    ///
+    /// As with `Input::input_buffer()`, the input buffer may contain any value
+    /// that went through the triple buffer in the past, and you must call
+    /// `publish()` to send your updates to the consumer.
+    ///
+    pub fn input_buffer(&mut self) -> &mut T {
+        // This is safe because the indices of the input, output and back
+        // buffers are always distinct, and the shared state is not Sync.
+        let input_ptr = self.shared.buffers[self.input_idx.get()].get();
+        unsafe { &mut *input_ptr }
+    }
+
+    /// Publish the current input buffer, checking for overwrites
+    ///
+    /// Returns `true` if a value which was not read by the consumer was
+    /// overwritten.
+    ///
+    pub fn publish(&mut self) -> bool {
+        // Orderings do not matter, as the shared state is not Sync
+        atomic::publish_swap(
+            &self.shared.back_info,
+            &mut self.input_idx,
+            Ordering::Relaxed,
+        )
+        .is_dirty()
+    }
+}
+
+/// Consumer interface to the single-threaded triple buffer
+#[derive(Debug)]
+pub struct LocalOutput<T> {
+    /// Reference-counted shared state
+    shared: Rc<LocalSharedState<T>>,
+
+    /// Index of the output buffer (which is private to the consumer)
+    output_idx: BufferIndex,
+}
+//
+impl<T> LocalOutput<T> {
+    /// Access the latest value from the triple buffer
+    pub fn read(&mut self) -> &T {
+        self.update();
+        self.peek_output_buffer()
+    }
+
+    /// Tell whether an updated value has been submitted by the producer
+    pub fn updated(&self) -> bool {
+        self.shared.back_info.load(Ordering::Relaxed).is_dirty()
+    }
+
+    /// Access the output buffer directly, without fetching updates
+    pub fn peek_output_buffer(&self) -> &T {
+        // This is safe because only `update()` and `output_buffer()`, which
+        // take `&mut self`, can invalidate this reference.
+        let output_ptr = self.shared.buffers[self.output_idx.get()].get();
+        unsafe { &*output_ptr }
+    }
+
+    /// Access the output buffer directly, in writable mode
+    ///
+    /// As with `Output::output_buffer()`, any change that you make to the
+    /// output buffer will be lost on the next update.
+    ///
+    pub fn output_buffer(&mut self) -> &mut T {
+        // This is safe for the same reason as `LocalInput::input_buffer()`
+        let output_ptr = self.shared.buffers[self.output_idx.get()].get();
+        unsafe { &mut *output_ptr }
+    }
+
+    /// Update the output buffer
+    ///
+    /// Check if the producer submitted a new data version, and if one is
+    /// available, update our output buffer to use it. Return a flag that tells
+    /// you whether such an update was carried out.
+    ///
+    pub fn update(&mut self) -> bool {
+        // Orderings do not matter, as the shared state is not Sync
+        atomic::update_swap(
+            &self.shared.back_info,
+            &mut self.output_idx,
+            Ordering::Relaxed,
+        )
+        .is_some()
+    }
+}
+
+/// Internal state of the single-threaded triple buffer
+#[derive(Debug)]
+struct LocalSharedState<T> {
+    /// Data storage buffers
+    buffers: [UnsafeCell<T>; 3],
+
+    /// Information about the current back-buffer state
+    back_info: LocalCell,
+}
+
+/// Unit tests
+#[cfg(test)]
+mod tests {
+    use super::LocalTripleBuffer;
+    use std::rc::Rc;
+
+    /// Check that the single-threaded triple buffer follows the usual protocol
+    #[test]
+    fn sequential() {
+        let (mut input, mut output) = LocalTripleBuffer::new(&Rc::new(0)).split();
+        assert!(input.consumed());
+        assert!(!output.updated());
+        assert_eq!(**output.read(), 0);
+
+        // Updates are not visible until they are fetched
+        assert!(!input.publish());
+        *input.input_buffer() = Rc::new(1);
+        assert!(input.publish());
+        assert!(!input.consumed());
+        assert!(output.updated());
+        assert_eq!(**output.peek_output_buffer(), 0);
+        assert_eq!(**output.read(), 1);
+        assert!(input.consumed());
+
+        // Buffers are recycled, so all three end up being visited
+        let mut seen = Vec::new();
+        for value in 2..8 {
+            input.write(Rc::new(value));
+            assert!(output.update());
+            assert_eq!(**output.output_buffer(), value);
+            seen.push(output.peek_output_buffer() as *const Rc<i32>);
+        }
+        seen.sort_unstable();
+        seen.dedup();
+        assert_eq!(seen.len(), 3);
+    }
+}
@@ -0,0 +1,203 @@
+//! Deterministic replay of producer/consumer interleavings, for unit tests
+//!
+//! The producer and consumer only interact through one atomic read-modify-write
+//! operation on the back-buffer information per publish or update. Between
+//! those, each side works on buffers that it has exclusive access to. Any
+//! execution of a triple buffer is thus equivalent, as far as sequentially
+//! consistent interleavings are concerned, to a sequence of whole producer and
+//! consumer operations, which can be replayed exactly on a single thread.
+//!
+//! This module provides a way to record such a sequence from a real concurrent
+//! run, and to replay it deterministically. When a user reports a bad
+//! interleaving, it can be written down as a `Schedule` like `"PPCPC"` and
+//! turned into a regression test. Weak memory effects are out of scope: those
+//! are the job of the concurrent stress tests.
+
+use crate::{Input, Output, TripleBuffer};
+use std::{
+    fmt::{self, Display},
+    str::FromStr,
+    sync::{Mutex, PoisonError},
+    thread,
+};
+
+/// Operation of a schedule
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub(crate) enum Step {
+    /// Run the next producer operation
+    Producer,
+
+    /// Run the next consumer operation
+    Consumer,
+}
+
+/// Interleaving of producer and consumer operations
+///
+/// The textual representation uses one character per operation: `P` for the
+/// producer, and `C` for the consumer.
+///
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub(crate) struct Schedule(pub(crate) Vec<Step>);
+//
+impl FromStr for Schedule {
+    type Err = char;
+
+    fn from_str(s: &str) -> Result<Self, char> {
+        s.chars()
+            .map(|c| match c {
+                'P' => Ok(Step::Producer),
+                'C' => Ok(Step::Consumer),
+                other => Err(other),
+            })
+            .collect::<Result<_, _>>()
+            .map(Self)
+    }
+}
+//
+impl Display for Schedule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for step in &self.0 {
+            f.write_str(match step {
+                Step::Producer => "P",
+                Step::Consumer => "C",
+            })?;
+        }
+        Ok(())
+    }
+}
+
+/// Replay a schedule on a single thread
+///
+/// `producer` is called for each `P` step and `consumer` for each `C` step.
+/// Each call should perform exactly one operation that publishes or fetches
+/// updates, e.g. a `write()` or a `read()`, so that the schedule describes the
+/// interleaving of these operations.
+///
+pub(crate) fn replay<T: Send>(
+    buf: TripleBuffer<T>,
+    schedule: &Schedule,
+    mut producer: impl FnMut(&mut Input<T>),
+    mut consumer: impl FnMut(&mut Output<T>),
+) {
+    let (mut input, mut output) = buf.split();
+    for step in &schedule.0 {
+        match step {
+            Step::Producer => producer(&mut input),
+            Step::Consumer => consumer(&mut output),
+        }
+    }
+}
+
+/// Run the producer and consumer concurrently, recording their interleaving
+///
+/// The producer and consumer operations are run `producer_steps` and
+/// `consumer_steps` times on two separate threads. Each operation is carried
+/// out under a lock that also protects the schedule, so that the recorded
+/// order is exactly the order in which operations took effect. Replaying the
+/// resulting schedule with the same operations reproduces the run.
+///
+pub(crate) fn record<T: Send>(
+    buf: TripleBuffer<T>,
+    producer_steps: usize,
+    mut producer: impl FnMut(&mut Input<T>) + Send,
+    consumer_steps: usize,
+    mut consumer: impl FnMut(&mut Output<T>) + Send,
+) -> Schedule {
+    let (mut input, mut output) = buf.split();
+    let schedule = Mutex::new(Schedule::default());
+    let run_step = |step, operation: &mut dyn FnMut()| {
+        let mut schedule = schedule.lock().unwrap_or_else(PoisonError::into_inner);
+        operation();
+        schedule.0.push(step);
+    };
+    thread::scope(|s| {
+        s.spawn(|| {
+            for _ in 0..producer_steps {
+                run_step(Step::Producer, &mut || producer(&mut input));
+                thread::yield_now();
+            }
+        });
+        for _ in 0..consumer_steps {
+            run_step(Step::Consumer, &mut || consumer(&mut output));
+            thread::yield_now();
+        }
+    });
+    schedule
+        .into_inner()
+        .unwrap_or_else(PoisonError::into_inner)
+}
+
+/// Unit tests
+#[cfg(test)]
+mod tests {
+    use super::{record, replay, Schedule, Step};
+    use crate::{Sequence, TripleBuffer};
+
+    /// Check that schedules can be written down and parsed back
+    #[test]
+    fn schedule_text() {
+        let schedule = "PPCP".parse::<Schedule>().unwrap();
+        assert_eq!(
+            schedule.0,
+            [
+                Step::Producer,
+                Step::Producer,
+                Step::Consumer,
+                Step::Producer
+            ]
+        );
+        assert_eq!(schedule.to_string(), "PPCP");
+        assert_eq!("PXC".parse::<Schedule>(), Err('X'));
+    }
+
+    /// Values observed by a consumer that reads after every operation
+    fn observe(schedule: &Schedule) -> Vec<(u32, Sequence)> {
+        let mut next_value = 0;
+        let mut observed = Vec::new();
+        replay(
+            TripleBuffer::new(&0),
+            schedule,
+            |input| {
+                next_value += 1;
+                input.write(next_value);
+            },
+            |output| {
+                let (&value, sequence) = output.read_with_sequence();
+                observed.push((value, sequence));
+            },
+        );
+        observed
+    }
+
+    /// Example regression test: the consumer sees the latest value, even when
+    /// the producer overwrote an update that it did not fetch yet
+    #[test]
+    fn overwrite_then_read() {
+        let observed = observe(&"CPPCCPC".parse().unwrap());
+        let values = observed.iter().map(|&(value, _)| value).collect::<Vec<_>>();
+        assert_eq!(values, [0, 2, 2, 3]);
+        assert!(observed.windows(2).all(|w| w[0].1 <= w[1].1));
+    }
+
+    /// Check that replaying a recorded run reproduces what the consumer saw
+    #[test]
+    fn record_and_replay() {
+        let mut next_value = 0;
+        let mut observed = Vec::new();
+        let schedule = record(
+            TripleBuffer::new(&0),
+            100,
+            |input| {
+                next_value += 1;
+                input.write(next_value);
+            },
+            100,
+            |output| {
+                let (&value, sequence) = output.read_with_sequence();
+                observed.push((value, sequence));
+            },
+        );
+        assert_eq!(schedule.0.len(), 200);
+        assert_eq!(observe(&schedule), observed);
+    }
+}
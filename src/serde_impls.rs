@@ -0,0 +1,114 @@
+//! Serialization support for triple buffers
+//!
+//! A triple buffer is serialized as the latest value that it contains, i.e.
+//! the value that the consumer would get by calling `Output::read()`. Values
+//! which were already superseded by a newer update are not persisted, and
+//! neither is the assignment of buffers to the producer and consumer, which
+//! is an implementation detail. Deserializing thus yields a fresh triple
+//! buffer, as built by `TripleBuffer::new()`, whose buffers all hold the
+//! persisted value.
+
+use crate::{atomic::BackInfoAtomic, TripleBuffer};
+use alloc::sync::Arc;
+use core::{fmt, marker::PhantomData, sync::atomic::Ordering};
+use serde::{de, ser, Deserialize, Deserializer, Serialize, Serializer};
+
+/// Name under which triple buffers are serialized
+const NAME: &str = "TripleBuffer";
+
+impl<T: Send> TripleBuffer<T> {
+    /// Access the value that `Output::read()` would return
+    ///
+    /// Returns `None` if the `Input` and `Output` of this triple buffer were
+    /// swapped with other ones via `split_mut()`, as the latest value is then
+    /// not well-defined.
+    ///
+    fn latest(&self) -> Option<&T> {
+        // Since we hold both ends of the triple buffer, no one else can access
+        // the shared state, and we can look into the back-buffer.
+        if !Arc::ptr_eq(&self.input.shared, &self.output.shared) {
+            return None;
+        }
+        let back_info = self.input.shared.back_info.load(Ordering::Relaxed);
+        let latest_idx = if back_info.is_dirty() {
+            back_info.index()
+        } else {
+            self.output.output_idx
+        };
+        let latest_ptr = self.input.shared.buffers[latest_idx.get()].data.get();
+        Some(unsafe { &*latest_ptr })
+    }
+}
+
+impl<T: Send + Serialize> Serialize for TripleBuffer<T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let latest = self.latest().ok_or_else(|| {
+            ser::Error::custom("Input and Output of this TripleBuffer were swapped with other ones")
+        })?;
+        serializer.serialize_newtype_struct(NAME, latest)
+    }
+}
+
+impl<'de, T: Clone + Send + Deserialize<'de>> Deserialize<'de> for TripleBuffer<T> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        /// Visitor that extracts the latest value of a serialized triple buffer
+        struct LatestVisitor<T>(PhantomData<T>);
+        //
+        impl<'de, T: Deserialize<'de>> de::Visitor<'de> for LatestVisitor<T> {
+            type Value = T;
+
+            fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                f.write_str("a triple buffer")
+            }
+
+            fn visit_newtype_struct<D: Deserializer<'de>>(
+                self,
+                deserializer: D,
+            ) -> Result<T, D::Error> {
+                T::deserialize(deserializer)
+            }
+        }
+
+        let latest = deserializer.deserialize_newtype_struct(NAME, LatestVisitor(PhantomData))?;
+        Ok(Self::new(&latest))
+    }
+}
+
+/// Unit tests
+#[cfg(test)]
+mod tests {
+    use crate::TripleBuffer;
+
+    /// Check that the latest value survives a round trip through serde
+    #[test]
+    fn round_trip() {
+        let round_trip = |buf: &TripleBuffer<Vec<u8>>| {
+            let json = serde_json::to_string(buf).unwrap();
+            serde_json::from_str::<TripleBuffer<Vec<u8>>>(&json).unwrap()
+        };
+
+        // Initial value
+        let mut buf = TripleBuffer::new(&vec![0]);
+        assert_eq!(serde_json::to_string(&buf).unwrap(), "[0]");
+        assert_eq!(round_trip(&buf), TripleBuffer::new(&vec![0]));
+
+        // Unread update
+        buf.input.write(vec![1]);
+        buf.input.write(vec![2]);
+        assert_eq!(round_trip(&buf), TripleBuffer::new(&vec![2]));
+
+        // Update that was already fetched
+        buf.output.read();
+        assert_eq!(round_trip(&buf), TripleBuffer::new(&vec![2]));
+    }
+
+    /// Check that mismatched halves are reported as a serialization error
+    #[test]
+    fn swapped_halves() {
+        let mut buf1 = TripleBuffer::new(&0);
+        let mut buf2 = TripleBuffer::new(&1);
+        core::mem::swap(buf1.split_mut().0, buf2.split_mut().0);
+        assert!(serde_json::to_string(&buf1).is_err());
+        assert!(serde_json::to_string(&buf2).is_err());
+    }
+}
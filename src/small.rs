@@ -0,0 +1,267 @@
+//! Triple buffer variant for small values, which live inside of an atomic
+//!
+//! When the data being shared fits in 32 bits, there is no need for three
+//! separate buffers: the value can be stored directly inside of an atomic
+//! integer, along with the dirty bit. Writes become a single atomic store, and
+//! reads become a single atomic load in the common case where no new value was
+//! published since the last read.
+
+use alloc::sync::Arc;
+use core::sync::atomic::{AtomicU64, Ordering};
+
+/// Bit which is set by the producer to signal that a value was published
+const DIRTY_BIT: u64 = 1 << 32;
+
+/// Mask used to extract the value from the atomic
+const VALUE_MASK: u64 = DIRTY_BIT - 1;
+
+/// Small value which can be stored inside of an `AtomicTripleBuffer`
+///
+/// This trait is implemented for primitive types which fit in 32 bits. It is
+/// sealed, as implementing it requires guarantees about the bit-level
+/// representation of the value which cannot be checked by the compiler.
+///
+pub trait AtomicPayload: Copy + Send + private::Sealed {
+    /// Convert the value into its bit-level representation
+    #[doc(hidden)]
+    fn into_bits(self) -> u32;
+
+    /// Convert back a bit-level representation produced by `into_bits()`
+    #[doc(hidden)]
+    fn from_bits(bits: u32) -> Self;
+}
+//
+mod private {
+    /// Prevents implementations of `AtomicPayload` outside of this crate
+    pub trait Sealed {}
+}
+//
+macro_rules! impl_atomic_payload_int {
+    ($($int:ty => $uint:ty),*) => {$(
+        impl private::Sealed for $int {}
+        //
+        impl AtomicPayload for $int {
+            fn into_bits(self) -> u32 {
+                self as $uint as u32
+            }
+
+            fn from_bits(bits: u32) -> Self {
+                bits as $uint as $int
+            }
+        }
+    )*};
+}
+impl_atomic_payload_int!(
+    u8 => u8, u16 => u16, u32 => u32,
+    i8 => u8, i16 => u16, i32 => u32
+);
+//
+impl private::Sealed for f32 {}
+//
+impl AtomicPayload for f32 {
+    fn into_bits(self) -> u32 {
+        self.to_bits()
+    }
+
+    fn from_bits(bits: u32) -> Self {
+        f32::from_bits(bits)
+    }
+}
+//
+impl private::Sealed for bool {}
+//
+impl AtomicPayload for bool {
+    fn into_bits(self) -> u32 {
+        self as u32
+    }
+
+    fn from_bits(bits: u32) -> Self {
+        bits != 0
+    }
+}
+//
+impl private::Sealed for char {}
+//
+impl AtomicPayload for char {
+    fn into_bits(self) -> u32 {
+        self as u32
+    }
+
+    fn from_bits(bits: u32) -> Self {
+        char::from_u32(bits).expect("Only valid chars are stored in the atomic")
+    }
+}
+
+/// Triple buffer for small values, which are stored inside of an atomic
+///
+/// This provides the same single-producer single-consumer "latest value"
+/// semantics as `TripleBuffer`, but for types which fit in 32 bits, the value
+/// is stored directly inside of an atomic integer instead of going through
+/// three separate buffers. This avoids the buffer indirection, and makes
+/// writing a single atomic store.
+///
+/// The price to pay is a restricted interface: as there are no buffers, there
+/// is no way to access the input or output buffer in place.
+///
+/// This type is only available on targets which support 64-bit atomics.
+///
+/// ```
+/// # use triple_buffer::AtomicTripleBuffer;
+/// let (mut input, mut output) = AtomicTripleBuffer::new(0u32).split();
+/// input.write(42);
+/// assert!(output.updated());
+/// assert_eq!(output.read(), 42);
+/// assert!(!output.updated());
+/// ```
+///
+#[derive(Debug)]
+pub struct AtomicTripleBuffer<T: AtomicPayload> {
+    /// Input object used by producers to send updates
+    input: AtomicInput<T>,
+
+    /// Output object used by consumers to read the current value
+    output: AtomicOutput<T>,
+}
+//
+impl<T: AtomicPayload> AtomicTripleBuffer<T> {
+    /// Construct an atomic triple buffer with a certain initial value
+    pub fn new(initial: T) -> Self {
+        let shared = Arc::new(AtomicU64::new(u64::from(initial.into_bits())));
+        Self {
+            input: AtomicInput {
+                shared: shared.clone(),
+                _payload: core::marker::PhantomData,
+            },
+            output: AtomicOutput {
+                shared,
+                _payload: core::marker::PhantomData,
+            },
+        }
+    }
+
+    /// Extract input and output of the atomic triple buffer
+    pub fn split(self) -> (AtomicInput<T>, AtomicOutput<T>) {
+        (self.input, self.output)
+    }
+}
+//
+impl<T: AtomicPayload + Default> Default for AtomicTripleBuffer<T> {
+    fn default() -> Self {
+        Self::new(T::default())
+    }
+}
+
+/// Producer interface to the atomic triple buffer
+#[derive(Debug)]
+pub struct AtomicInput<T: AtomicPayload> {
+    /// Atomic integer holding the value and the dirty bit
+    shared: Arc<AtomicU64>,
+
+    /// Type of the values stored inside of the atomic
+    _payload: core::marker::PhantomData<T>,
+}
+//
+impl<T: AtomicPayload> AtomicInput<T> {
+    /// Write a new value into the triple buffer
+    pub fn write(&mut self, value: T) {
+        self.shared
+            .store(u64::from(value.into_bits()) | DIRTY_BIT, Ordering::Release);
+    }
+
+    /// Check if the consumer has fetched our last submission yet
+    ///
+    /// This method is only intended for diagnostics purposes. Please do not let
+    /// it inform your decision of sending or not sending a value, as that would
+    /// effectively be building a very poor spinlock-based double buffer
+    /// implementation. If what you truly need is a double buffer, build
+    /// yourself a proper blocking one instead of wasting CPU time.
+    ///
+    pub fn consumed(&self) -> bool {
+        self.shared.load(Ordering::Relaxed) & DIRTY_BIT == 0
+    }
+}
+
+/// Consumer interface to the atomic triple buffer
+#[derive(Debug)]
+pub struct AtomicOutput<T: AtomicPayload> {
+    /// Atomic integer holding the value and the dirty bit
+    shared: Arc<AtomicU64>,
+
+    /// Type of the values stored inside of the atomic
+    _payload: core::marker::PhantomData<T>,
+}
+//
+impl<T: AtomicPayload> AtomicOutput<T> {
+    /// Read the latest value from the triple buffer
+    ///
+    /// If a new value was published since the last read, this also clears the
+    /// dirty bit, so that `updated()` returns `false` until the next write.
+    ///
+    pub fn read(&mut self) -> T {
+        // In the common case where no update was published, a load is enough
+        let mut bits = self.shared.load(Ordering::Acquire);
+        if bits & DIRTY_BIT != 0 {
+            // Otherwise, we must clear the dirty bit. The producer may have
+            // published another value in the meantime, so we return the value
+            // that was there when the dirty bit got cleared.
+            bits = self.shared.fetch_and(!DIRTY_BIT, Ordering::Acquire);
+        }
+        T::from_bits((bits & VALUE_MASK) as u32)
+    }
+
+    /// Tell whether an updated value has been submitted by the producer
+    ///
+    /// This method is mainly intended for diagnostics purposes. Please do not
+    /// let it inform your decision of reading a value or not, as that would
+    /// effectively be building a very poor spinlock-based double buffer
+    /// implementation. If what you truly need is a double buffer, build
+    /// yourself a proper blocking one instead of wasting CPU time.
+    ///
+    pub fn updated(&self) -> bool {
+        self.shared.load(Ordering::Relaxed) & DIRTY_BIT != 0
+    }
+}
+
+/// Unit tests
+#[cfg(test)]
+mod tests {
+    use super::{AtomicPayload, AtomicTripleBuffer};
+    use core::fmt::Debug;
+
+    /// Check that values survive a round trip through the atomic
+    #[test]
+    fn payload_round_trip() {
+        fn check<T: AtomicPayload + Debug + PartialEq>(values: &[T]) {
+            for &value in values {
+                assert_eq!(T::from_bits(value.into_bits()), value);
+            }
+        }
+        check(&[0u8, 1, u8::MAX]);
+        check(&[0u16, 1, u16::MAX]);
+        check(&[0u32, 1, u32::MAX]);
+        check(&[0i8, -1, i8::MIN, i8::MAX]);
+        check(&[0i16, -1, i16::MIN, i16::MAX]);
+        check(&[0i32, -1, i32::MIN, i32::MAX]);
+        check(&[0.0f32, -1.5, f32::INFINITY, f32::MIN_POSITIVE]);
+        check(&[false, true]);
+        check(&['a', 'é', char::MAX]);
+    }
+
+    /// Check the single-threaded behavior of the atomic triple buffer
+    #[test]
+    fn sequential() {
+        let (mut input, mut output) = AtomicTripleBuffer::new(-1i32).split();
+        assert!(input.consumed());
+        assert!(!output.updated());
+        assert_eq!(output.read(), -1);
+
+        input.write(1);
+        input.write(-2);
+        assert!(!input.consumed());
+        assert!(output.updated());
+        assert_eq!(output.read(), -2);
+        assert!(input.consumed());
+        assert!(!output.updated());
+        assert_eq!(output.read(), -2);
+    }
+}
@@ -0,0 +1,73 @@
+//! Check that debug-build panics are reported at the caller's location
+//!
+//! This needs a custom panic hook, which is global to the process. It thus
+//! lives in its own test binary, where it cannot interfere with other tests.
+
+#![cfg(debug_assertions)]
+
+use std::{
+    cell::Cell,
+    panic::{self, AssertUnwindSafe},
+    sync::Once,
+};
+use triple_buffer::TripleBuffer;
+
+thread_local! {
+    /// Line of this file where the last panic of the current thread occurred
+    static PANIC_LINE: Cell<Option<u32>> = const { Cell::new(None) };
+}
+
+/// Run a function which is expected to panic, and tell on which line of this
+/// file the panic was reported, if any
+fn panic_line<R>(f: impl FnOnce() -> R) -> Option<u32> {
+    static INSTALL_HOOK: Once = Once::new();
+    INSTALL_HOOK.call_once(|| {
+        let default_hook = panic::take_hook();
+        panic::set_hook(Box::new(move |info| {
+            let location = info.location().unwrap();
+            if location.file() == file!() {
+                PANIC_LINE.with(|line| line.set(Some(location.line())));
+            } else {
+                default_hook(info);
+            }
+        }));
+    });
+    PANIC_LINE.with(|line| line.set(None));
+    assert!(panic::catch_unwind(AssertUnwindSafe(f)).is_err());
+    PANIC_LINE.with(Cell::get)
+}
+
+#[test]
+fn forbidden_overwrite_location() {
+    let (mut input, _output) = TripleBuffer::new(&0).split();
+    input.forbid_overwrites(true);
+    input.write(1);
+
+    let expected_line = line!() + 1;
+    let line = panic_line(|| input.write(2));
+    assert_eq!(line, Some(expected_line));
+}
+
+#[test]
+fn check_order_location() {
+    let (mut input, mut output) = TripleBuffer::new(&0).split();
+    output.check_order(|previous, current| current > previous);
+    input.write(2);
+    output.read();
+
+    // All methods that fetch updates report the panic at the caller
+    input.write(1);
+    let expected_line = line!() + 1;
+    let line = panic_line(|| output.read());
+    assert_eq!(line, Some(expected_line));
+
+    input.write(0);
+    let expected_line = line!() + 1;
+    let line = panic_line(|| output.poll_and_read());
+    assert_eq!(line, Some(expected_line));
+
+    input.write(-1);
+    let expected_line = line!() + 1;
+    let line = panic_line(|| output.read_mut());
+    assert_eq!(line, Some(expected_line));
+}
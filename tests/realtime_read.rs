@@ -0,0 +1,65 @@
+//! Check that the read path is suitable for real-time threads
+//!
+//! This counts memory allocations with a custom global allocator, which
+//! affects the whole process. It thus lives in its own test binary.
+
+use std::{
+    alloc::{GlobalAlloc, Layout, System},
+    cell::Cell,
+};
+use triple_buffer::TripleBuffer;
+
+thread_local! {
+    /// Number of memory allocations performed by the current thread
+    static ALLOCATIONS: Cell<usize> = const { Cell::new(0) };
+}
+
+/// Global allocator that counts allocations made by each thread
+struct CountingAllocator;
+//
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let _ = ALLOCATIONS.try_with(|count| count.set(count.get() + 1));
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+}
+//
+#[global_allocator]
+static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+/// Number of memory allocations performed by the current thread so far
+fn allocations() -> usize {
+    ALLOCATIONS.with(Cell::get)
+}
+
+#[test]
+fn realtime_read() {
+    let (mut input, mut output) = TripleBuffer::new(&vec![0.0f32; 64]).split();
+
+    // Warm up any lazily initialized thread-local state
+    input.write(vec![1.0; 64]);
+    output.read();
+
+    // Reading never allocates, no matter whether an update is available
+    let before = allocations();
+    for _ in 0..3 {
+        output.update();
+        output.read();
+        output.peek_output_buffer();
+        output.updated();
+        output.sequence();
+    }
+    assert_eq!(allocations(), before);
+
+    // Neither do in-place writes
+    for _ in 0..3 {
+        input.input_buffer().fill(2.0);
+        input.publish();
+        output.read();
+    }
+    assert_eq!(allocations(), before);
+}